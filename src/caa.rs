@@ -0,0 +1,284 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::dns_structs::*;
+use crate::name;
+
+/// property tag of a CAA record (RFC 8659, RFC 9495)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CaaTag {
+    Issue,
+    IssueWild,
+    Iodef,
+    ContactEmail,
+    ContactPhone,
+    IssueMail,
+    Unknown(String),
+}
+
+impl FromStr for CaaTag {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.len() > 15 || !s.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(format!("invalid CAA tag `{s}`"));
+        }
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "issue" => CaaTag::Issue,
+            "issuewild" => CaaTag::IssueWild,
+            "iodef" => CaaTag::Iodef,
+            "contactemail" => CaaTag::ContactEmail,
+            "contactphone" => CaaTag::ContactPhone,
+            "issuemail" => CaaTag::IssueMail,
+            _ => CaaTag::Unknown(s.to_string()),
+        })
+    }
+}
+
+impl Display for CaaTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaaTag::Issue => write!(f, "issue"),
+            CaaTag::IssueWild => write!(f, "issuewild"),
+            CaaTag::Iodef => write!(f, "iodef"),
+            CaaTag::ContactEmail => write!(f, "contactemail"),
+            CaaTag::ContactPhone => write!(f, "contactphone"),
+            CaaTag::IssueMail => write!(f, "issuemail"),
+            CaaTag::Unknown(tag) => write!(f, "{tag}"),
+        }
+    }
+}
+
+/// value of `issue`, `issuewild` and `issuemail`: the CA and its parameters
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct CaaIssuer {
+    /// issuer domain name of the CA, `None` forbids issuance
+    pub issuer: Option<String>,
+    /// `tag=value` parameters like `account` or `validationmethods`
+    pub parameters: Vec<(String, String)>,
+}
+
+impl CaaIssuer {
+    pub fn new(issuer: &str) -> Self {
+        Self { issuer: Some(issuer.to_string()), parameters: Vec::new() }
+    }
+
+    fn parameter(&self, tag: &str) -> Option<&str> {
+        self.parameters.iter().find(|(t, _)| t.eq_ignore_ascii_case(tag)).map(|(_, v)| v.as_str())
+    }
+
+    /// `account=`, the account at the CA (RFC 8657)
+    pub fn account(&self) -> Option<&str> {
+        self.parameter("account")
+    }
+
+    /// `validationmethods=`, the domain validation methods the CA may use (RFC 8657)
+    pub fn validation_methods(&self) -> Vec<&str> {
+        self.parameter("validationmethods").map(|v| v.split(',').collect()).unwrap_or_default()
+    }
+}
+
+impl FromStr for CaaIssuer {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (issuer, parameters) = s.split_once(';').unwrap_or((s, ""));
+        let issuer = issuer.trim();
+        let mut ret = Self::default();
+        if !issuer.is_empty() {
+            let valid_label = |l: &str| {
+                !l.is_empty()
+                    && l.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+                    && !l.starts_with('-')
+                    && !l.ends_with('-')
+            };
+            if !issuer.split('.').all(valid_label) {
+                return Err(format!("invalid issuer domain name `{issuer}`"));
+            }
+            ret.issuer = Some(issuer.to_string());
+        }
+        for parameter in parameters.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (tag, value) = parameter.split_once('=').ok_or_else(|| format!("CAA parameter `{parameter}` is not tag=value"))?;
+            let (tag, value) = (tag.trim(), value.trim());
+            if tag.is_empty() || !tag.bytes().all(|b| b.is_ascii_alphanumeric()) {
+                return Err(format!("invalid CAA parameter tag `{tag}`"));
+            }
+            if !value.bytes().all(|b| (0x21..=0x7e).contains(&b) && b != b';') {
+                return Err(format!("invalid value of CAA parameter `{tag}`"));
+            }
+            ret.parameters.push((tag.to_string(), value.to_string()));
+        }
+        Ok(ret)
+    }
+}
+
+impl Display for CaaIssuer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(issuer) = &self.issuer {
+            write!(f, "{issuer}")?;
+        } else if self.parameters.is_empty() {
+            write!(f, ";")?;
+        }
+        for (tag, value) in &self.parameters {
+            write!(f, "; {tag}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// a CAA property with its value parsed
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CaaProperty {
+    Issue(CaaIssuer),
+    IssueWild(CaaIssuer),
+    IssueMail(CaaIssuer),
+    /// `mailto:`, `http:` or `https:` URL incident reports go to
+    Iodef(String),
+    ContactEmail(String),
+    ContactPhone(String),
+    Unknown { tag: String, value: String },
+}
+
+impl CaaProperty {
+    pub fn tag(&self) -> CaaTag {
+        match self {
+            CaaProperty::Issue(_) => CaaTag::Issue,
+            CaaProperty::IssueWild(_) => CaaTag::IssueWild,
+            CaaProperty::IssueMail(_) => CaaTag::IssueMail,
+            CaaProperty::Iodef(_) => CaaTag::Iodef,
+            CaaProperty::ContactEmail(_) => CaaTag::ContactEmail,
+            CaaProperty::ContactPhone(_) => CaaTag::ContactPhone,
+            CaaProperty::Unknown { tag, .. } => CaaTag::Unknown(tag.clone()),
+        }
+    }
+
+    /// parse the unquoted `value` of a property with `tag`
+    pub fn parse(tag: &CaaTag, value: &str) -> Result<Self, String> {
+        Ok(match tag {
+            CaaTag::Issue => CaaProperty::Issue(value.parse()?),
+            CaaTag::IssueWild => CaaProperty::IssueWild(value.parse()?),
+            CaaTag::IssueMail => CaaProperty::IssueMail(value.parse()?),
+            CaaTag::Iodef => {
+                check_iodef(value)?;
+                CaaProperty::Iodef(value.to_string())
+            }
+            CaaTag::ContactEmail if !value.contains('@') => return Err(format!("`{value}` is not an email address")),
+            CaaTag::ContactEmail => CaaProperty::ContactEmail(value.to_string()),
+            CaaTag::ContactPhone => CaaProperty::ContactPhone(value.to_string()),
+            CaaTag::Unknown(tag) => CaaProperty::Unknown { tag: tag.clone(), value: value.to_string() },
+        })
+    }
+
+    fn value(&self) -> String {
+        match self {
+            CaaProperty::Issue(v) | CaaProperty::IssueWild(v) | CaaProperty::IssueMail(v) => v.to_string(),
+            CaaProperty::Iodef(v) | CaaProperty::ContactEmail(v) | CaaProperty::ContactPhone(v) => v.clone(),
+            CaaProperty::Unknown { value, .. } => value.clone(),
+        }
+    }
+}
+
+/// iodef URLs are `mailto:` an address or `http:`/`https:` with a host (RFC 8659 section 4.4)
+fn check_iodef(value: &str) -> Result<(), String> {
+    let invalid = || Err(format!("`{value}` is not a mailto, http or https URL"));
+    let Some((scheme, rest)) = value.split_once(':') else {
+        return invalid();
+    };
+    match scheme.to_ascii_lowercase().as_str() {
+        "mailto" => {
+            let (local, domain) = rest.split_once('@').unwrap_or_default();
+            if local.is_empty() || domain.is_empty() {
+                return invalid();
+            }
+        }
+        "http" | "https" => {
+            let host = rest.strip_prefix("//").and_then(|r| r.split(['/', '?', '#']).next()).unwrap_or_default();
+            if host.is_empty() {
+                return invalid();
+            }
+        }
+        _ => return invalid(),
+    }
+    Ok(())
+}
+
+impl Caa {
+    /// issuer critical flag, CAs may not issue when they do not understand the tag
+    pub const CRITICAL: u8 = 0x80;
+
+    /// CAA record at `name` holding `property`
+    pub fn new(name: &str, property: &CaaProperty, critical: bool) -> Self {
+        Caa {
+            name: name.to_string(),
+            flags: if critical { Self::CRITICAL } else { 0 },
+            tag: property.tag().to_string(),
+            value: property.value(),
+            ttl: None,
+        }
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.flags & Self::CRITICAL != 0
+    }
+
+    pub fn caa_tag(&self) -> Result<CaaTag, String> {
+        self.tag.parse()
+    }
+
+    /// the property with its value parsed and checked
+    pub fn property(&self) -> Result<CaaProperty, String> {
+        CaaProperty::parse(&self.caa_tag()?, &self.value)
+    }
+}
+
+/// CAA values in JSON written by older versions still carry their quotes
+#[cfg(feature="serde")]
+pub(crate) fn unquoted<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+    Ok(crate::record::joined_text(&value).unwrap_or(value))
+}
+
+impl DnsRecord {
+    /// the CAA records that apply to `name`, those of the name itself or of
+    /// the closest parent with any, climbing up to the apex (RFC 8659 section 3)
+    pub fn relevant_caa(&self, name: &str) -> Vec<&Caa> {
+        let apex = self.zone_origin();
+        let mut current = name::fqdn(name, &apex);
+        loop {
+            let found: Vec<&Caa> = self
+                .caa
+                .iter()
+                .flatten()
+                .filter(|c| name::canonical_cmp(&name::fqdn(&c.name, &apex), &current).is_eq())
+                .collect();
+            if !found.is_empty() || name::canonical_cmp(&current, &apex).is_eq() || !name::is_subdomain(&current, &apex) {
+                return found;
+            }
+            current = match current.split_once('.') {
+                Some((_, parent)) if !parent.is_empty() => parent.to_string(),
+                _ => return found,
+            };
+        }
+    }
+
+    /// true when the CA with issuer domain `issuer` may issue a certificate
+    /// for `name`, a wildcard certificate when `wildcard` is set
+    ///
+    /// no CAA record lets every CA issue, a critical property with an
+    /// unknown tag lets none. `issuewild` takes precedence for wildcards,
+    /// malformed `issue` values authorise nobody
+    pub fn caa_permits(&self, name: &str, issuer: &str, wildcard: bool) -> bool {
+        let records = self.relevant_caa(name);
+        if records.iter().any(|c| c.is_critical() && matches!(c.caa_tag(), Ok(CaaTag::Unknown(_)) | Err(_))) {
+            return false;
+        }
+        let has = |tag: CaaTag| records.iter().any(|c| c.caa_tag().is_ok_and(|t| t == tag));
+        let tag = if wildcard && has(CaaTag::IssueWild) { CaaTag::IssueWild } else { CaaTag::Issue };
+        if !has(tag.clone()) {
+            return true;
+        }
+        let issuer = issuer.strip_suffix('.').unwrap_or(issuer);
+        records.iter().filter(|c| c.caa_tag().is_ok_and(|t| t == tag)).any(|c| match c.property() {
+            Ok(CaaProperty::Issue(v) | CaaProperty::IssueWild(v)) => v.issuer.is_some_and(|i| i.eq_ignore_ascii_case(issuer)),
+            _ => false,
+        })
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::delegation;
+use crate::dns_structs::*;
+use crate::name;
+use crate::record::Record;
+
+/// how serious a finding is, errors keep a server from loading the zone
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// problem `DnsRecord::validate` found
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub enum FindingKind {
+    /// the zone has no SOA record
+    MissingSoa,
+    /// the SOA owner is not the zone origin
+    SoaNotAtApex,
+    /// the zone has no NS records at the apex
+    MissingApexNs,
+    /// a CNAME shares its owner with other data or another CNAME
+    CnameAndOtherData,
+    /// a CNAME at the zone apex
+    CnameAtApex,
+    /// an NS, MX or SRV target is a CNAME in the zone
+    TargetIsCname,
+    /// the owner is not inside the zone
+    OutOfZone,
+    /// the same record appears more than once
+    DuplicateRecord,
+    /// the records of one RRset have different TTLs
+    TtlMismatch,
+    /// the RDATA can not be encoded
+    InvalidRecord,
+    /// a nameserver inside the child zone it serves has no A or AAAA glue
+    MissingGlue,
+    /// data at or below a delegation point that the cut hides
+    OccludedData,
+    /// A or AAAA below a delegation point that no NS record refers to
+    StrayGlue,
+}
+
+impl FindingKind {
+    /// severity of the finding, following what named-checkzone refuses
+    pub fn severity(&self) -> Severity {
+        match self {
+            FindingKind::OutOfZone
+            | FindingKind::DuplicateRecord
+            | FindingKind::TtlMismatch
+            | FindingKind::OccludedData
+            | FindingKind::StrayGlue => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// one problem in a zone
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub severity: Severity,
+    /// absolute owner name
+    pub name: String,
+    pub rtype: RecordType,
+    pub message: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}: {}", self.name, self.rtype, self.message)
+    }
+}
+
+/// result of `DnsRecord::validate`
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct ValidationReport {
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    /// no finding is an error
+    pub fn is_valid(&self) -> bool {
+        self.findings.iter().all(|f| f.severity < Severity::Error)
+    }
+
+    pub(crate) fn push(&mut self, kind: FindingKind, name: &str, rtype: RecordType, message: String) {
+        self.findings.push(Finding { kind, severity: kind.severity(), name: name.to_string(), rtype, message });
+    }
+}
+
+/// records of one owner and type, with the owner absolute
+struct Rrset<'a> {
+    name: String,
+    records: Vec<&'a Record>,
+}
+
+impl DnsRecord {
+    /// check that the zone can be loaded, the errors named-checkzone reports
+    ///
+    /// the zone needs a SOA and NS records at the apex, a CNAME can not
+    /// share its owner with other data or sit at the apex, NS, MX and SRV
+    /// targets can not be CNAMEs, and every owner has to be inside the zone.
+    /// delegations need glue for nameservers inside the child zone.
+    /// duplicate records, RRsets with mixed TTLs, data hidden below a
+    /// delegation and glue no NS refers to are warnings.
+    /// more than one SOA is reported by the parser, the zone holds the last
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let apex = self.zone_origin();
+        let records = self.records();
+
+        let mut rrsets: BTreeMap<(String, RecordType), Rrset> = BTreeMap::new();
+        for record in &records {
+            let owner = name::fqdn(record.name(), &apex);
+            let key = (owner.to_ascii_lowercase(), record.record_type());
+            rrsets.entry(key).or_insert_with(|| Rrset { name: owner, records: Vec::new() }).records.push(record);
+        }
+        let has = |owner: &str, rtype: RecordType| rrsets.contains_key(&(owner.to_ascii_lowercase(), rtype));
+
+        match &self.soa {
+            None => report.push(FindingKind::MissingSoa, &apex, RecordType::Soa, "zone has no SOA record".to_string()),
+            Some(soa) if !name::canonical_cmp(&name::fqdn(&soa.name, &apex), &apex).is_eq() => {
+                let message = format!("SOA owner is not the zone origin {apex}");
+                report.push(FindingKind::SoaNotAtApex, &name::fqdn(&soa.name, &apex), RecordType::Soa, message);
+            }
+            Some(_) => {}
+        }
+        if !has(&apex, RecordType::Ns) {
+            report.push(FindingKind::MissingApexNs, &apex, RecordType::Ns, "zone has no NS records at the apex".to_string());
+        }
+
+        for ((owner, rtype), rrset) in &rrsets {
+            if !name::is_subdomain(&rrset.name, &apex) {
+                report.push(FindingKind::OutOfZone, &rrset.name, *rtype, format!("owner is outside the zone {apex}"));
+            }
+            if *rtype == RecordType::Cname {
+                if name::canonical_cmp(&rrset.name, &apex).is_eq() {
+                    report.push(FindingKind::CnameAtApex, &rrset.name, *rtype, "CNAME at the zone apex".to_string());
+                }
+                let others: Vec<String> = rrsets
+                    .keys()
+                    .filter(|(o, t)| o == owner && !matches!(t, RecordType::Cname | RecordType::Rrsig | RecordType::Nsec))
+                    .map(|(_, t)| t.to_string())
+                    .collect();
+                if !others.is_empty() {
+                    let message = format!("CNAME and other data ({})", others.join(", "));
+                    report.push(FindingKind::CnameAndOtherData, &rrset.name, *rtype, message);
+                }
+                if rrset.records.len() > 1 {
+                    report.push(FindingKind::CnameAndOtherData, &rrset.name, *rtype, "more than one CNAME".to_string());
+                }
+            }
+
+            let mut seen: Vec<Vec<u8>> = Vec::new();
+            for record in &rrset.records {
+                // a `{time}` serial is only filled in by generate
+                if *rtype != RecordType::Soa {
+                    match record.rdata_wire(&apex) {
+                        Ok(rdata) if seen.contains(&rdata) => {
+                            report.push(FindingKind::DuplicateRecord, &rrset.name, *rtype, "duplicate record".to_string());
+                        }
+                        Ok(rdata) => seen.push(rdata),
+                        Err(e) => report.push(FindingKind::InvalidRecord, &rrset.name, *rtype, e),
+                    }
+                }
+                if let Record::Caa(caa) = record
+                    && let Err(e) = caa.property()
+                {
+                    report.push(FindingKind::InvalidRecord, &rrset.name, *rtype, e);
+                }
+                let target = match record {
+                    Record::Ns(r) => &r.host,
+                    Record::Mx(r) => &r.host,
+                    Record::Srv(r) => &r.target,
+                    _ => continue,
+                };
+                let target = name::fqdn(target, &apex);
+                if name::is_subdomain(&target, &apex) && has(&target, RecordType::Cname) {
+                    let message = format!("target {target} is a CNAME");
+                    report.push(FindingKind::TargetIsCname, &rrset.name, *rtype, message);
+                }
+            }
+
+            // RRSIGs of one owner cover different types and keep their own TTLs
+            if *rtype != RecordType::Rrsig {
+                let mut ttls: Vec<u32> = rrset.records.iter().map(|r| self.effective_ttl(r.ttl())).collect();
+                ttls.sort_unstable();
+                ttls.dedup();
+                if ttls.len() > 1 {
+                    let ttls: Vec<String> = ttls.iter().map(u32::to_string).collect();
+                    let message = format!("records have different TTLs ({})", ttls.join(", "));
+                    report.push(FindingKind::TtlMismatch, &rrset.name, *rtype, message);
+                }
+            }
+        }
+        delegation::check(self, &apex, &mut report);
+        report
+    }
+}
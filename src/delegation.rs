@@ -0,0 +1,97 @@
+use crate::check::{FindingKind, ValidationReport};
+use crate::dns_structs::*;
+use crate::name;
+use crate::record::Record;
+
+/// zone cut below the apex, the NS records hand the subdomain to other servers
+#[derive(Debug, PartialEq, Clone)]
+pub struct Delegation {
+    /// absolute name of the child zone
+    pub name: String,
+    /// absolute host names of the NS records
+    pub nameservers: Vec<String>,
+}
+
+impl Delegation {
+    /// true when `owner` is the delegation point or below it
+    pub fn contains(&self, owner: &str) -> bool {
+        name::is_subdomain(owner, &self.name)
+    }
+
+    /// nameservers inside the child zone, they need A or AAAA glue in the parent
+    pub fn in_bailiwick(&self) -> impl Iterator<Item = &String> {
+        self.nameservers.iter().filter(|ns| self.contains(ns))
+    }
+}
+
+impl DnsRecord {
+    /// delegation points of the zone in canonical order
+    ///
+    /// NS records below the apex start a delegation, unless they are
+    /// themselves below another one and so hidden by it
+    pub fn delegations(&self) -> Vec<Delegation> {
+        let apex = self.zone_origin();
+        let mut ret: Vec<Delegation> = Vec::new();
+        for ns in self.ns.iter().flatten() {
+            let owner = name::fqdn(&ns.name, &apex);
+            if name::canonical_cmp(&owner, &apex).is_eq() || !name::is_subdomain(&owner, &apex) {
+                continue;
+            }
+            let host = name::fqdn(&ns.host, &apex);
+            match ret.iter_mut().find(|d| name::canonical_cmp(&d.name, &owner).is_eq()) {
+                Some(delegation) => delegation.nameservers.push(host),
+                None => ret.push(Delegation { name: owner, nameservers: vec![host] }),
+            }
+        }
+        ret.sort_by(|a, b| name::canonical_cmp(&a.name, &b.name));
+        let cuts = ret.clone();
+        ret.retain(|d| !cuts.iter().any(|c| c.contains(&d.name) && !name::canonical_cmp(&c.name, &d.name).is_eq()));
+        ret
+    }
+}
+
+/// in-bailiwick nameservers without glue, data hidden below a cut and
+/// glue no NS record refers to
+pub(crate) fn check(zone: &DnsRecord, apex: &str, report: &mut ValidationReport) {
+    let delegations = zone.delegations();
+    if delegations.is_empty() {
+        return;
+    }
+    let records = zone.records();
+    let owners: Vec<(String, &Record)> = records.iter().map(|r| (name::fqdn(r.name(), apex), r)).collect();
+    let is_address = |r: &Record| matches!(r, Record::A(_) | Record::Aaaa(_));
+    let has_address = |host: &str| owners.iter().any(|(o, r)| is_address(r) && name::canonical_cmp(o, host).is_eq());
+
+    for delegation in &delegations {
+        for ns in delegation.in_bailiwick() {
+            if !has_address(ns) {
+                let message = format!("no A or AAAA glue for in-bailiwick nameserver {ns}");
+                report.push(FindingKind::MissingGlue, &delegation.name, RecordType::Ns, message);
+            }
+        }
+    }
+
+    // glue has to be named by an NS record, of the apex or of any delegation
+    let nameservers: Vec<String> = zone.ns.iter().flatten().map(|ns| name::fqdn(&ns.host, apex)).collect();
+    let is_glue = |owner: &str| nameservers.iter().any(|ns| name::canonical_cmp(ns, owner).is_eq());
+    for (owner, record) in &owners {
+        let Some(delegation) = delegations.iter().find(|d| d.contains(owner)) else {
+            continue;
+        };
+        let rtype = record.record_type();
+        if name::canonical_cmp(owner, &delegation.name).is_eq() {
+            // a nameserver named like the child zone needs its glue at the cut
+            let glue = is_address(record) && is_glue(owner);
+            if !glue && !matches!(rtype, RecordType::Ns | RecordType::Ds | RecordType::Nsec | RecordType::Rrsig) {
+                let message = format!("{rtype} at the delegation point {} is hidden by the cut", delegation.name);
+                report.push(FindingKind::OccludedData, owner, rtype, message);
+            }
+        } else if !is_address(record) {
+            let message = format!("{rtype} below the delegation point {} is hidden by the cut", delegation.name);
+            report.push(FindingKind::OccludedData, owner, rtype, message);
+        } else if !is_glue(owner) {
+            let message = format!("glue below {} that no NS record refers to", delegation.name);
+            report.push(FindingKind::StrayGlue, owner, rtype, message);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::dns_structs::*;
+
+/// authoritative server a generated zone file is meant for
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Dialect {
+    /// the zone as it is, BIND reads every record type this crate knows
+    #[default]
+    Bind,
+    /// `$TTL` is always written
+    Nsd,
+    /// every record carries an explicit ttl
+    Knot,
+    /// the PowerDNS bind backend, which signs zones itself, so RRSIG, NSEC,
+    /// NSEC3 and NSEC3PARAM records are left out
+    PowerDns,
+}
+
+impl Dialect {
+    /// true when the server loads records of `rtype` from a zone file
+    pub fn supports(&self, rtype: RecordType) -> bool {
+        match self {
+            Dialect::PowerDns => !matches!(
+                rtype,
+                RecordType::Rrsig | RecordType::Nsec | RecordType::Nsec3 | RecordType::Nsec3param
+            ),
+            _ => !matches!(rtype, RecordType::Unknown(_)),
+        }
+    }
+
+    /// `zone` adapted to the server, with a warning for every record left out
+    ///
+    /// servers other than BIND need a numeric SOA serial, `{time}` is
+    /// still filled in by the generator
+    pub fn adapt(&self, zone: &DnsRecord) -> Result<(DnsRecord, Vec<GenerateWarning>), String> {
+        let mut zone = zone.clone();
+        if *self == Dialect::Bind {
+            return Ok((zone, Vec::new()));
+        }
+        if let Some(Serial::String(s)) = zone.soa.as_ref().map(|soa| &soa.serial)
+            && s != "{time}"
+        {
+            return Err(format!("SOA serial `{s}` is not a number"));
+        }
+
+        let mut warnings = Vec::new();
+        for record in zone.records() {
+            let rtype = record.record_type();
+            if !self.supports(rtype) {
+                warnings.push(GenerateWarning {
+                    record: format!("{} {rtype}", record.name()),
+                    message: format!("{rtype} records are not supported by {self:?}"),
+                });
+            }
+        }
+        zone.retain(|r| self.supports(r.record_type()));
+
+        match self {
+            Dialect::Nsd => zone.ttl = Some(zone.effective_ttl(None)),
+            Dialect::Knot => {
+                let default = zone.effective_ttl(None);
+                let records = zone.records();
+                zone.retain(|_| false);
+                for mut record in records {
+                    record.set_ttl(Some(record.ttl().unwrap_or(default)));
+                    zone.push(record);
+                }
+            }
+            _ => {}
+        }
+        Ok((zone, warnings))
+    }
+}
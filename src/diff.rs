@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dns_structs::*;
+use crate::generator::{self, NameStyle};
+use crate::name;
+use crate::record::Record;
+
+/// how one record differs between two zones, records have absolute
+/// owner names and an explicit ttl
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="serde", serde(tag = "change", rename_all = "lowercase"))]
+pub enum RecordChange {
+    Added { record: Record },
+    Removed { record: Record },
+    /// same owner and type, other data
+    Modified { old: Record, new: Record },
+    /// same data, only the ttl differs
+    Ttl { old: Record, new: Record },
+}
+
+impl Display for RecordChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line = generator::record_line;
+        match self {
+            RecordChange::Added { record } => write!(f, "+{}", line(record)),
+            RecordChange::Removed { record } => write!(f, "-{}", line(record)),
+            RecordChange::Modified { old, new } => write!(f, "-{}\n+{}", line(old), line(new)),
+            RecordChange::Ttl { old, new } => write!(f, "-{}\n+{}\t; ttl only", line(old), line(new)),
+        }
+    }
+}
+
+/// changes to the records of one owner and type
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct RrsetDiff {
+    /// absolute owner name
+    pub name: String,
+    pub rtype: RecordType,
+    pub changes: Vec<RecordChange>,
+}
+
+/// differences between two zones, `DnsRecord::diff`
+///
+/// `Display` renders it like a unified diff, one `@@ name TYPE @@` hunk
+/// per RRset with `-` lines for old records and `+` lines for new ones
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct ZoneDiff {
+    pub rrsets: Vec<RrsetDiff>,
+}
+
+impl ZoneDiff {
+    pub fn is_empty(&self) -> bool {
+        self.rrsets.is_empty()
+    }
+
+    pub fn changes(&self) -> impl Iterator<Item = &RecordChange> {
+        self.rrsets.iter().flat_map(|r| &r.changes)
+    }
+}
+
+impl Display for ZoneDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for rrset in &self.rrsets {
+            writeln!(f, "@@ {} {} @@", rrset.name, rrset.rtype)?;
+            for change in &rrset.changes {
+                writeln!(f, "{change}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// record keyed by its RDATA in canonical wire format
+pub(crate) struct Entry {
+    pub rdata: Vec<u8>,
+    pub record: Record,
+}
+
+/// records of the zone by lower-cased absolute owner and type, with
+/// absolute names, the effective ttl and duplicates removed
+pub(crate) fn rrsets(zone: &DnsRecord) -> BTreeMap<(String, u16), (String, Vec<Entry>)> {
+    let origin = zone.zone_origin();
+    let mut ret: BTreeMap<(String, u16), (String, Vec<Entry>)> = BTreeMap::new();
+    for mut record in generator::normalize_names(zone.clone(), NameStyle::Absolute).records() {
+        record.set_ttl(Some(zone.effective_ttl(record.ttl())));
+        if let Record::Soa(soa) = &mut record {
+            soa.mname = name::fqdn(&soa.mname, &origin);
+            soa.rname = name::fqdn(&soa.rname, &origin);
+        }
+        let owner = name::fqdn(record.name(), &origin);
+        // data that has no wire form is compared as written
+        let rdata = record.rdata_wire(&origin).unwrap_or_else(|_| format!("{record:?}").into_bytes());
+        let key = (owner.to_ascii_lowercase(), record.record_type().code());
+        let (_, entries) = ret.entry(key).or_insert_with(|| (owner, Vec::new()));
+        if !entries.iter().any(|e| e.rdata == rdata) {
+            entries.push(Entry { rdata, record });
+        }
+    }
+    ret
+}
+
+impl DnsRecord {
+    /// what changes from `self` to `other`, grouped by owner and type in
+    /// canonical order
+    ///
+    /// names are compared absolute and ignoring case, RDATA in its canonical
+    /// wire form. records only in one zone are paired up as modified within
+    /// their RRset, records in both that differ in ttl alone are `Ttl` changes
+    pub fn diff(&self, other: &DnsRecord) -> ZoneDiff {
+        let mut old = rrsets(self);
+        let mut new = rrsets(other);
+        let mut keys: Vec<(String, u16)> = old.keys().chain(new.keys()).cloned().collect();
+        keys.sort_by(|a, b| name::canonical_cmp(&a.0, &b.0).then(a.1.cmp(&b.1)));
+        keys.dedup();
+
+        let mut ret = ZoneDiff::default();
+        for key in keys {
+            let (old_name, old_entries) = old.remove(&key).unwrap_or_default();
+            let (new_name, new_entries) = new.remove(&key).unwrap_or_default();
+            let old_rdata: Vec<Vec<u8>> = old_entries.iter().map(|e| e.rdata.clone()).collect();
+            let mut changes = Vec::new();
+            let mut removed = Vec::new();
+            for entry in old_entries {
+                match new_entries.iter().find(|e| e.rdata == entry.rdata) {
+                    Some(kept) if kept.record.ttl() != entry.record.ttl() => {
+                        changes.push(RecordChange::Ttl { old: entry.record, new: kept.record.clone() });
+                    }
+                    Some(_) => {}
+                    None => removed.push(entry.record),
+                }
+            }
+            let mut added = new_entries.into_iter().filter(|e| !old_rdata.contains(&e.rdata)).map(|e| e.record);
+            let mut removed = removed.into_iter();
+            loop {
+                match (removed.next(), added.next()) {
+                    (Some(old), Some(new)) => changes.push(RecordChange::Modified { old, new }),
+                    (Some(record), None) => changes.push(RecordChange::Removed { record }),
+                    (None, Some(record)) => changes.push(RecordChange::Added { record }),
+                    (None, None) => break,
+                }
+            }
+            if !changes.is_empty() {
+                let name = if new_name.is_empty() { old_name } else { new_name };
+                ret.rrsets.push(RrsetDiff { name, rtype: RecordType::from_code(key.1), changes });
+            }
+        }
+        ret
+    }
+}
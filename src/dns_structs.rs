@@ -48,6 +48,245 @@ impl Display for Serial {
     }
 }
 
+/// DNSSEC algorithm numbers (IANA "DNS Security Algorithm Numbers")
+///
+/// parsed from either the numeric or the mnemonic form,
+/// serialized as the number like in zone files
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="serde", serde(into = "u8", try_from = "NumberOrMnemonic"))]
+pub enum DnssecAlgorithm {
+    RsaMd5,
+    Dh,
+    Dsa,
+    RsaSha1,
+    DsaNsec3Sha1,
+    RsaSha1Nsec3Sha1,
+    RsaSha256,
+    RsaSha512,
+    EccGost,
+    #[default]
+    EcdsaP256Sha256,
+    EcdsaP384Sha384,
+    Ed25519,
+    Ed448,
+    PrivateDns,
+    PrivateOid,
+    Unknown(u8),
+}
+
+impl DnssecAlgorithm {
+    pub fn mnemonic(&self) -> String {
+        match self {
+            DnssecAlgorithm::RsaMd5 => "RSAMD5".to_string(),
+            DnssecAlgorithm::Dh => "DH".to_string(),
+            DnssecAlgorithm::Dsa => "DSA".to_string(),
+            DnssecAlgorithm::RsaSha1 => "RSASHA1".to_string(),
+            DnssecAlgorithm::DsaNsec3Sha1 => "DSA-NSEC3-SHA1".to_string(),
+            DnssecAlgorithm::RsaSha1Nsec3Sha1 => "RSASHA1-NSEC3-SHA1".to_string(),
+            DnssecAlgorithm::RsaSha256 => "RSASHA256".to_string(),
+            DnssecAlgorithm::RsaSha512 => "RSASHA512".to_string(),
+            DnssecAlgorithm::EccGost => "ECC-GOST".to_string(),
+            DnssecAlgorithm::EcdsaP256Sha256 => "ECDSAP256SHA256".to_string(),
+            DnssecAlgorithm::EcdsaP384Sha384 => "ECDSAP384SHA384".to_string(),
+            DnssecAlgorithm::Ed25519 => "ED25519".to_string(),
+            DnssecAlgorithm::Ed448 => "ED448".to_string(),
+            DnssecAlgorithm::PrivateDns => "PRIVATEDNS".to_string(),
+            DnssecAlgorithm::PrivateOid => "PRIVATEOID".to_string(),
+            DnssecAlgorithm::Unknown(n) => n.to_string(),
+        }
+    }
+}
+
+impl From<u8> for DnssecAlgorithm {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::RsaMd5,
+            2 => Self::Dh,
+            3 => Self::Dsa,
+            5 => Self::RsaSha1,
+            6 => Self::DsaNsec3Sha1,
+            7 => Self::RsaSha1Nsec3Sha1,
+            8 => Self::RsaSha256,
+            10 => Self::RsaSha512,
+            12 => Self::EccGost,
+            13 => Self::EcdsaP256Sha256,
+            14 => Self::EcdsaP384Sha384,
+            15 => Self::Ed25519,
+            16 => Self::Ed448,
+            253 => Self::PrivateDns,
+            254 => Self::PrivateOid,
+            n => Self::Unknown(n),
+        }
+    }
+}
+
+impl From<DnssecAlgorithm> for u8 {
+    fn from(value: DnssecAlgorithm) -> Self {
+        match value {
+            DnssecAlgorithm::RsaMd5 => 1,
+            DnssecAlgorithm::Dh => 2,
+            DnssecAlgorithm::Dsa => 3,
+            DnssecAlgorithm::RsaSha1 => 5,
+            DnssecAlgorithm::DsaNsec3Sha1 => 6,
+            DnssecAlgorithm::RsaSha1Nsec3Sha1 => 7,
+            DnssecAlgorithm::RsaSha256 => 8,
+            DnssecAlgorithm::RsaSha512 => 10,
+            DnssecAlgorithm::EccGost => 12,
+            DnssecAlgorithm::EcdsaP256Sha256 => 13,
+            DnssecAlgorithm::EcdsaP384Sha384 => 14,
+            DnssecAlgorithm::Ed25519 => 15,
+            DnssecAlgorithm::Ed448 => 16,
+            DnssecAlgorithm::PrivateDns => 253,
+            DnssecAlgorithm::PrivateOid => 254,
+            DnssecAlgorithm::Unknown(n) => n,
+        }
+    }
+}
+
+impl FromStr for DnssecAlgorithm {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(num) = s.parse::<u8>() {
+            return Ok(Self::from(num));
+        }
+        (0..=255u8)
+            .map(Self::from)
+            .find(|alg| !matches!(alg, Self::Unknown(_)) && alg.mnemonic().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown DNSSEC algorithm `{s}`"))
+    }
+}
+
+impl Display for DnssecAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.mnemonic())
+    }
+}
+
+/// DS digest types (IANA "Delegation Signer (DS) Resource Record Digest Algorithms")
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="serde", serde(into = "u8", try_from = "NumberOrMnemonic"))]
+pub enum DigestType {
+    Sha1,
+    #[default]
+    Sha256,
+    Gost,
+    Sha384,
+    Unknown(u8),
+}
+
+impl DigestType {
+    pub fn mnemonic(&self) -> String {
+        match self {
+            DigestType::Sha1 => "SHA-1".to_string(),
+            DigestType::Sha256 => "SHA-256".to_string(),
+            DigestType::Gost => "GOST".to_string(),
+            DigestType::Sha384 => "SHA-384".to_string(),
+            DigestType::Unknown(n) => n.to_string(),
+        }
+    }
+
+    /// length in octets of a digest of this type, `None` when unknown
+    pub fn digest_len(&self) -> Option<usize> {
+        match self {
+            DigestType::Sha1 => Some(20),
+            DigestType::Sha256 | DigestType::Gost => Some(32),
+            DigestType::Sha384 => Some(48),
+            DigestType::Unknown(_) => None,
+        }
+    }
+}
+
+impl From<u8> for DigestType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Sha1,
+            2 => Self::Sha256,
+            3 => Self::Gost,
+            4 => Self::Sha384,
+            n => Self::Unknown(n),
+        }
+    }
+}
+
+impl From<DigestType> for u8 {
+    fn from(value: DigestType) -> Self {
+        match value {
+            DigestType::Sha1 => 1,
+            DigestType::Sha256 => 2,
+            DigestType::Gost => 3,
+            DigestType::Sha384 => 4,
+            DigestType::Unknown(n) => n,
+        }
+    }
+}
+
+impl FromStr for DigestType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(num) = s.parse::<u8>() {
+            return Ok(Self::from(num));
+        }
+        let stripped = s.replace('-', "");
+        (1..=4u8)
+            .map(Self::from)
+            .find(|t| t.mnemonic().replace('-', "").eq_ignore_ascii_case(&stripped))
+            .ok_or_else(|| format!("unknown DS digest type `{s}`"))
+    }
+}
+
+impl Display for DigestType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.mnemonic())
+    }
+}
+
+/// serde input of the numeric registry enums, `8` or `"RSASHA256"`
+#[cfg(feature="serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrMnemonic {
+    Number(u8),
+    Mnemonic(String),
+}
+
+#[cfg(feature="serde")]
+impl TryFrom<NumberOrMnemonic> for DnssecAlgorithm {
+    type Error = String;
+    fn try_from(value: NumberOrMnemonic) -> Result<Self, Self::Error> {
+        match value {
+            NumberOrMnemonic::Number(n) => Ok(n.into()),
+            NumberOrMnemonic::Mnemonic(s) => s.parse(),
+        }
+    }
+}
+
+#[cfg(feature="serde")]
+impl TryFrom<NumberOrMnemonic> for DigestType {
+    type Error = String;
+    fn try_from(value: NumberOrMnemonic) -> Result<Self, Self::Error> {
+        match value {
+            NumberOrMnemonic::Number(n) => Ok(n.into()),
+            NumberOrMnemonic::Mnemonic(s) => s.parse(),
+        }
+    }
+}
+
+#[cfg(feature="paperclip")]
+impl paperclip::v2::schema::TypedData for DnssecAlgorithm {
+    fn data_type() -> paperclip::v2::models::DataType {
+        paperclip::v2::models::DataType::Integer
+    }
+}
+
+#[cfg(feature="paperclip")]
+impl paperclip::v2::schema::TypedData for DigestType {
+    fn data_type() -> paperclip::v2::models::DataType {
+        paperclip::v2::models::DataType::Integer
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Clone)]
 #[cfg_attr(feature="paperclip", derive(Apiv2Schema))]
 #[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
@@ -66,6 +305,7 @@ pub struct Soa {
 }
 
 impl Soa {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(ttl: u32, mname: String, rname: String, serial: String, refresh: u32, retry: u32, expire: u32, minimum: u32) -> Self {
         Self {
         name: "@".to_owned(),
@@ -193,6 +433,8 @@ pub struct Caa {
     pub name: String,
     pub flags: u8,
     pub tag: String,
+    /// property value without the quotes of the zone file
+    #[cfg_attr(feature="serde", serde(deserialize_with = "crate::caa::unquoted"))]
     pub value: String,
     #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
     pub ttl: Option<u32>,
@@ -204,12 +446,313 @@ pub struct Caa {
 #[cfg_attr(feature="apistos", derive(ApiComponent, JsonSchema))]
 pub struct Ds {
     pub name: String,
-    pub key_tag: String,
-    pub algorithm: String,
-    pub digest_type: String,
-    pub digest: String,
+    pub key_tag: u16,
+    #[cfg_attr(feature="apistos", schemars(with = "u8"))]
+    pub algorithm: DnssecAlgorithm,
+    #[cfg_attr(feature="apistos", schemars(with = "u8"))]
+    pub digest_type: DigestType,
+    #[cfg_attr(feature="serde", serde(with = "crate::encoding::hex_serde"))]
+    #[cfg_attr(feature="apistos", schemars(with = "String"))]
+    pub digest: Vec<u8>,
     #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
     pub ttl: Option<u32>,
+    /// RDATA as written when the record could not be parsed,
+    /// the typed fields are left at their defaults then
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub raw: Option<RawRdata>,
+}
+
+/// RDATA of a record kept as written, with the reason it could not be parsed
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="paperclip", derive(Apiv2Schema))]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="apistos", derive(ApiComponent, JsonSchema))]
+pub struct RawRdata {
+    pub rdata: String,
+    pub error: String,
+}
+
+impl Ds {
+    /// DS record of a legacy zone that could not be parsed, kept as written
+    pub fn legacy(name: String, rdata: String, ttl: Option<u32>, error: String) -> Self {
+        Ds { name, ttl, raw: Some(RawRdata { rdata, error }), ..Default::default() }
+    }
+
+    /// why the record is malformed, `None` for a valid DS
+    pub fn error(&self) -> Option<String> {
+        self.validate().err()
+    }
+
+    /// check the digest length against the digest type,
+    /// digests of unknown types are accepted as long as they are not empty
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(raw) = &self.raw {
+            return Err(raw.error.clone());
+        }
+        match self.digest_type.digest_len() {
+            Some(len) if len != self.digest.len() => Err(format!(
+                "DS digest of type {} must be {} octets, got {}",
+                self.digest_type, len, self.digest.len()
+            )),
+            None if self.digest.is_empty() => Err("DS digest is empty".to_owned()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="paperclip", derive(Apiv2Schema))]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="apistos", derive(ApiComponent, JsonSchema))]
+pub struct Dnskey {
+    pub name: String,
+    pub flags: u16,
+    pub protocol: u8,
+    #[cfg_attr(feature="apistos", schemars(with = "u8"))]
+    pub algorithm: DnssecAlgorithm,
+    #[cfg_attr(feature="serde", serde(with = "crate::encoding::base64_serde"))]
+    #[cfg_attr(feature="apistos", schemars(with = "String"))]
+    pub public_key: Vec<u8>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub ttl: Option<u32>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="paperclip", derive(Apiv2Schema))]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="apistos", derive(ApiComponent, JsonSchema))]
+pub struct Rrsig {
+    pub name: String,
+    pub type_covered: RecordType,
+    #[cfg_attr(feature="apistos", schemars(with = "u8"))]
+    pub algorithm: DnssecAlgorithm,
+    pub labels: u8,
+    pub original_ttl: u32,
+    /// seconds since the epoch, modulo 2^32 (RFC 4034 section 3.1.5)
+    pub expiration: u32,
+    /// seconds since the epoch, modulo 2^32 (RFC 4034 section 3.1.5)
+    pub inception: u32,
+    pub key_tag: u16,
+    pub signer_name: String,
+    #[cfg_attr(feature="serde", serde(with = "crate::encoding::base64_serde"))]
+    #[cfg_attr(feature="apistos", schemars(with = "String"))]
+    pub signature: Vec<u8>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub ttl: Option<u32>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="paperclip", derive(Apiv2Schema))]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="apistos", derive(ApiComponent, JsonSchema))]
+pub struct Nsec {
+    pub name: String,
+    pub next_domain: String,
+    pub types: Vec<RecordType>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub ttl: Option<u32>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="paperclip", derive(Apiv2Schema))]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="apistos", derive(ApiComponent, JsonSchema))]
+pub struct Nsec3 {
+    pub name: String,
+    pub hash_algorithm: u8,
+    pub flags: u8,
+    pub iterations: u16,
+    #[cfg_attr(feature="serde", serde(with = "crate::encoding::hex_serde"))]
+    #[cfg_attr(feature="apistos", schemars(with = "String"))]
+    pub salt: Vec<u8>,
+    /// raw hash of the next owner name, printed in base32hex
+    #[cfg_attr(feature="serde", serde(with = "crate::encoding::hex_serde"))]
+    #[cfg_attr(feature="apistos", schemars(with = "String"))]
+    pub next_hashed: Vec<u8>,
+    pub types: Vec<RecordType>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub ttl: Option<u32>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="paperclip", derive(Apiv2Schema))]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="apistos", derive(ApiComponent, JsonSchema))]
+pub struct Nsec3param {
+    pub name: String,
+    pub hash_algorithm: u8,
+    pub flags: u8,
+    pub iterations: u16,
+    #[cfg_attr(feature="serde", serde(with = "crate::encoding::hex_serde"))]
+    #[cfg_attr(feature="apistos", schemars(with = "String"))]
+    pub salt: Vec<u8>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub ttl: Option<u32>,
+}
+
+/// record the parser could not represent, with the reason
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct ParseWarning {
+    pub record: String,
+    pub message: String,
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: `{}`", self.message, self.record.trim())
+    }
+}
+
+/// record the generator left out for the target server, with the reason
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct GenerateWarning {
+    pub record: String,
+    pub message: String,
+}
+
+impl Display for GenerateWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: `{}`", self.message, self.record)
+    }
+}
+
+/// ZONEMD record of RFC 8976, a message digest over the zone content
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="paperclip", derive(Apiv2Schema))]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="apistos", derive(ApiComponent, JsonSchema))]
+pub struct Zonemd {
+    pub name: String,
+    pub serial: u32,
+    pub scheme: u8,
+    pub hash_algorithm: u8,
+    #[cfg_attr(feature="serde", serde(with = "crate::encoding::hex_serde"))]
+    #[cfg_attr(feature="apistos", schemars(with = "String"))]
+    pub digest: Vec<u8>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub ttl: Option<u32>,
+}
+
+/// record types known to DnsRecord,
+/// other types only show up in NSEC type bitmaps as `Unknown`
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="serde", serde(into = "String", try_from = "String"))]
+pub enum RecordType {
+    Soa,
+    Ns,
+    #[default]
+    A,
+    Aaaa,
+    Cname,
+    Mx,
+    Txt,
+    Ptr,
+    Srv,
+    Spf,
+    Caa,
+    Ds,
+    Dnskey,
+    Rrsig,
+    Nsec,
+    Nsec3,
+    Nsec3param,
+    Zonemd,
+    Unknown(u16),
+}
+
+const RECORD_TYPES: &[(RecordType, u16, &str)] = &[
+    (RecordType::A, 1, "A"),
+    (RecordType::Ns, 2, "NS"),
+    (RecordType::Cname, 5, "CNAME"),
+    (RecordType::Soa, 6, "SOA"),
+    (RecordType::Ptr, 12, "PTR"),
+    (RecordType::Mx, 15, "MX"),
+    (RecordType::Txt, 16, "TXT"),
+    (RecordType::Aaaa, 28, "AAAA"),
+    (RecordType::Srv, 33, "SRV"),
+    (RecordType::Ds, 43, "DS"),
+    (RecordType::Rrsig, 46, "RRSIG"),
+    (RecordType::Nsec, 47, "NSEC"),
+    (RecordType::Dnskey, 48, "DNSKEY"),
+    (RecordType::Nsec3, 50, "NSEC3"),
+    (RecordType::Nsec3param, 51, "NSEC3PARAM"),
+    (RecordType::Zonemd, 63, "ZONEMD"),
+    (RecordType::Spf, 99, "SPF"),
+    (RecordType::Caa, 257, "CAA"),
+];
+
+impl RecordType {
+    /// numeric TYPE value from the IANA registry
+    pub fn code(&self) -> u16 {
+        match self {
+            RecordType::Unknown(code) => *code,
+            known => RECORD_TYPES.iter().find(|(t, _, _)| t == known).map_or(0, |(_, code, _)| *code),
+        }
+    }
+
+    pub fn from_code(code: u16) -> Self {
+        RECORD_TYPES
+            .iter()
+            .find(|(_, c, _)| *c == code)
+            .map_or(RecordType::Unknown(code), |(t, _, _)| *t)
+    }
+}
+
+/// mnemonics and the generic `TYPE123` form of RFC 3597
+impl FromStr for RecordType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((t, _, _)) = RECORD_TYPES.iter().find(|(_, _, m)| m.eq_ignore_ascii_case(s)) {
+            return Ok(*t);
+        }
+        s.get(..4)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("TYPE"))
+            .and_then(|_| s[4..].parse::<u16>().ok())
+            .map(Self::from_code)
+            .ok_or_else(|| format!("unknown record type `{s}`"))
+    }
+}
+
+impl Display for RecordType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match RECORD_TYPES.iter().find(|(t, _, _)| t == self) {
+            Some((_, _, mnemonic)) => write!(f, "{}", mnemonic),
+            None => write!(f, "TYPE{}", self.code()),
+        }
+    }
+}
+
+impl From<RecordType> for String {
+    fn from(value: RecordType) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for RecordType {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(feature="paperclip")]
+impl paperclip::v2::schema::TypedData for RecordType {
+    fn data_type() -> paperclip::v2::models::DataType {
+        paperclip::v2::models::DataType::String
+    }
+}
+
+#[cfg(feature="apistos")]
+impl JsonSchema for RecordType {
+    fn schema_name() -> String {
+        "RecordType".to_owned()
+    }
+    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(generator)
+    }
 }
 
 #[derive(Debug, Default, PartialEq, Clone)]
@@ -246,6 +789,18 @@ pub struct DnsRecord {
     pub caa: Option<Vec<Caa>>,
     #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
     pub ds: Option<Vec<Ds>>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub dnskey: Option<Vec<Dnskey>>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub rrsig: Option<Vec<Rrsig>>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub nsec: Option<Vec<Nsec>>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub nsec3: Option<Vec<Nsec3>>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub nsec3param: Option<Vec<Nsec3param>>,
+    #[cfg_attr(feature="serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub zonemd: Option<Vec<Zonemd>>,
 }
 
 impl DnsRecord {
@@ -272,23 +827,25 @@ impl DnsRecord {
             && self.spf.is_none()
             && self.caa.is_none()
             && self.ds.is_none()
+            && self.dnskey.is_none()
+            && self.rrsig.is_none()
+            && self.nsec.is_none()
+            && self.nsec3.is_none()
+            && self.nsec3param.is_none()
+            && self.zonemd.is_none()
     }
 }
 
 pub const DEFAULT_TEMPLATE: &str = r#"; Zone: {zone}
-; Exported  (yyyy-mm-ddThh:mm:ss.sssZ): {datetime}
-
+{#if exported}; Exported  (yyyy-mm-ddThh:mm:ss.sssZ): {exported}
+{/if}
 {$origin}
 {$ttl}
 
+{#if soa}
 ; SOA Record
-{name} {ttl}	IN	SOA	{mname}{rname}(
-{serial} ;serial
-{refresh} ;refresh
-{retry} ;retry
-{expire} ;expire
-{minimum} ;minimum ttl
-)
+{soa}
+{/if}
 
 ; NS Records
 {ns}
@@ -323,4 +880,22 @@ pub const DEFAULT_TEMPLATE: &str = r#"; Zone: {zone}
 ; DS Records
 {ds}
 
+; DNSKEY Records
+{dnskey}
+
+; NSEC3PARAM Records
+{nsec3param}
+
+; NSEC Records
+{nsec}
+
+; NSEC3 Records
+{nsec3}
+
+; RRSIG Records
+{rrsig}
+
+; ZONEMD Records
+{zonemd}
+
 "#;
@@ -0,0 +1,83 @@
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384};
+
+use crate::dns_structs::*;
+use crate::name;
+use crate::record::Record;
+
+impl Dnskey {
+    /// DNSKEY flag bit 7, the key may sign zone data
+    pub const ZONE_KEY: u16 = 0x0100;
+    /// DNSKEY flag bit 15, secure entry point (usually a KSK)
+    pub const SEP: u16 = 0x0001;
+
+    pub fn is_zone_key(&self) -> bool {
+        self.flags & Self::ZONE_KEY != 0
+    }
+
+    pub fn is_sep(&self) -> bool {
+        self.flags & Self::SEP != 0
+    }
+
+    /// key tag of RFC 4034 appendix B
+    pub fn key_tag(&self) -> u16 {
+        if self.algorithm == DnssecAlgorithm::RsaMd5 {
+            // most significant 16 of the least significant 24 bits of the modulus
+            let key = &self.public_key;
+            return match key.len() {
+                0..=2 => 0,
+                l => u16::from_be_bytes([key[l - 3], key[l - 2]]),
+            };
+        }
+        let mut rdata = Vec::with_capacity(4 + self.public_key.len());
+        rdata.extend(self.flags.to_be_bytes());
+        rdata.push(self.protocol);
+        rdata.push(self.algorithm.into());
+        rdata.extend(&self.public_key);
+        let mut ac: u32 = 0;
+        for (i, b) in rdata.iter().enumerate() {
+            ac += if i & 1 == 1 { *b as u32 } else { (*b as u32) << 8 };
+        }
+        ac += (ac >> 16) & 0xffff;
+        (ac & 0xffff) as u16
+    }
+
+    /// DS record for this key (RFC 4034 section 5.1.4),
+    /// the owner is made absolute against `origin` so the result fits the parent zone
+    pub fn to_ds(&self, origin: &str, digest_type: DigestType) -> Result<Ds, String> {
+        let owner = name::fqdn(&self.name, origin);
+        let mut data = name::to_wire(&owner, true)?;
+        data.extend(Record::Dnskey(self.clone()).rdata_wire(origin)?);
+        let digest = match digest_type {
+            DigestType::Sha1 => Sha1::digest(&data).to_vec(),
+            DigestType::Sha256 => Sha256::digest(&data).to_vec(),
+            DigestType::Sha384 => Sha384::digest(&data).to_vec(),
+            other => return Err(format!("unsupported DS digest type {other}")),
+        };
+        Ok(Ds {
+            name: owner,
+            key_tag: self.key_tag(),
+            algorithm: self.algorithm,
+            digest_type,
+            digest,
+            ttl: None,
+            raw: None,
+        })
+    }
+}
+
+impl DnsRecord {
+    /// DS records for every DNSKEY with the SEP flag, one per requested digest type
+    ///
+    /// owners are fully qualified so the records can go straight into the parent zone
+    pub fn compute_ds(&self, digest_types: &[DigestType]) -> Result<Vec<Ds>, String> {
+        let origin = self.zone_origin();
+        let mut ret = Vec::new();
+        for key in self.dnskey.iter().flatten().filter(|k| k.is_zone_key() && k.is_sep()) {
+            for digest_type in digest_types {
+                ret.push(key.to_ds(&origin, *digest_type)?);
+            }
+        }
+        Ok(ret)
+    }
+}
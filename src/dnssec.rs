@@ -0,0 +1,209 @@
+use std::collections::BTreeSet;
+
+use sha1::{Digest, Sha1};
+
+use crate::dns_structs::*;
+use crate::name;
+use crate::record::Record;
+
+/// records of one owner name and type in canonical form and order
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Rrset {
+    /// absolute owner name as written in the zone
+    pub name: String,
+    pub rtype: RecordType,
+    pub ttl: u32,
+    pub rdata: Vec<Vec<u8>>,
+}
+
+impl DnsRecord {
+    /// in-zone RRsets in canonical order, the TTL is the one of the first record
+    pub(crate) fn rrsets(&self) -> Result<Vec<Rrset>, String> {
+        let mut ret: Vec<Rrset> = Vec::new();
+        for rr in self.canonical_records()? {
+            let rtype = RecordType::from_code(rr.rtype);
+            match ret.last_mut() {
+                Some(last) if last.rtype == rtype && name::canonical_cmp(&last.name, &rr.name).is_eq() => {
+                    last.rdata.push(rr.rdata);
+                }
+                _ => ret.push(Rrset { name: rr.name, rtype, ttl: rr.ttl, rdata: vec![rr.rdata] }),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+/// layout of the zone: apex and delegation points
+pub(crate) struct ZoneCuts {
+    pub apex: String,
+    pub delegations: Vec<String>,
+}
+
+impl ZoneCuts {
+    pub fn new(apex: &str, rrsets: &[Rrset]) -> Self {
+        let delegations = rrsets
+            .iter()
+            .filter(|r| r.rtype == RecordType::Ns && !name::canonical_cmp(&r.name, apex).is_eq())
+            .map(|r| r.name.clone())
+            .collect();
+        Self { apex: apex.to_string(), delegations }
+    }
+
+    pub fn is_delegation(&self, owner: &str) -> bool {
+        self.delegations.iter().any(|d| name::canonical_cmp(d, owner).is_eq())
+    }
+
+    /// name strictly below a delegation point, glue or occluded data
+    pub fn is_occluded(&self, owner: &str) -> bool {
+        self.delegations
+            .iter()
+            .any(|d| name::is_subdomain(owner, d) && !name::canonical_cmp(d, owner).is_eq())
+    }
+
+    /// the zone is authoritative for this RRset and signs it
+    pub fn is_signed(&self, rrset: &Rrset) -> bool {
+        if self.is_occluded(&rrset.name) {
+            return false;
+        }
+        if self.is_delegation(&rrset.name) {
+            return matches!(rrset.rtype, RecordType::Ds | RecordType::Nsec);
+        }
+        true
+    }
+
+    /// types an NSEC or NSEC3 record lists for the name, before RRSIG and NSEC are added
+    pub fn authoritative_types(&self, owner: &str, rrsets: &[Rrset]) -> Vec<RecordType> {
+        let at_delegation = self.is_delegation(owner);
+        rrsets
+            .iter()
+            .filter(|r| name::canonical_cmp(&r.name, owner).is_eq())
+            .map(|r| r.rtype)
+            .filter(|t| !at_delegation || matches!(t, RecordType::Ns | RecordType::Ds))
+            .collect()
+    }
+
+    /// type bitmap of the NSEC record at `owner`
+    pub fn nsec_types(&self, owner: &str, rrsets: &[Rrset]) -> Vec<RecordType> {
+        let mut types = self.authoritative_types(owner, rrsets);
+        types.extend([RecordType::Rrsig, RecordType::Nsec]);
+        types.sort_by_key(RecordType::code);
+        types.dedup();
+        types
+    }
+
+    /// type bitmap of the NSEC3 record for `owner`, RRSIG only when something there is signed
+    pub fn nsec3_types(&self, owner: &str, rrsets: &[Rrset]) -> Vec<RecordType> {
+        let mut types = self.authoritative_types(owner, rrsets);
+        let signed = rrsets
+            .iter()
+            .any(|r| name::canonical_cmp(&r.name, owner).is_eq() && self.is_signed(r));
+        if signed {
+            types.push(RecordType::Rrsig);
+        }
+        types.sort_by_key(RecordType::code);
+        types.dedup();
+        types
+    }
+
+    /// owner names that take part in the denial of existence chain, in canonical order
+    pub fn chain_names(&self, rrsets: &[Rrset]) -> Vec<String> {
+        let mut ret: Vec<String> = Vec::new();
+        for rrset in rrsets {
+            if self.is_occluded(&rrset.name) || rrset.rtype == RecordType::Nsec3 {
+                continue;
+            }
+            if !ret.last().is_some_and(|l| name::canonical_cmp(l, &rrset.name).is_eq()) {
+                ret.push(rrset.name.clone());
+            }
+        }
+        ret
+    }
+
+    /// names between the apex and existing names that own no records (RFC 5155 section 7.1)
+    pub fn empty_non_terminals(&self, names: &[String]) -> Vec<String> {
+        let existing: BTreeSet<String> = names.iter().map(|n| n.to_ascii_lowercase()).collect();
+        let mut ret = BTreeSet::new();
+        for owner in names {
+            let mut current = parent(owner);
+            while let Some(p) = current {
+                if !name::is_subdomain(&p, &self.apex) || name::canonical_cmp(&p, &self.apex).is_eq() {
+                    break;
+                }
+                if !existing.contains(&p.to_ascii_lowercase()) {
+                    ret.insert(p.to_ascii_lowercase());
+                }
+                current = parent(&p);
+            }
+        }
+        ret.into_iter().collect()
+    }
+}
+
+/// parent of an absolute name, `None` for the root
+pub(crate) fn parent(owner: &str) -> Option<String> {
+    let bytes = owner.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'.' => {
+                let rest = &owner[i + 1..];
+                return Some(if rest.is_empty() { ".".to_string() } else { rest.to_string() });
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// value of the RRSIG labels field, a leading wildcard label is not counted
+pub(crate) fn rrsig_labels(owner: &str) -> Result<u8, String> {
+    let labels = name::labels(owner)?;
+    let wildcard = labels.first().is_some_and(|l| l == b"*");
+    Ok((labels.len() - wildcard as usize) as u8)
+}
+
+/// data an RRSIG signature covers (RFC 4034 section 3.1.8.1),
+/// the signature field of `rrsig` is ignored
+pub(crate) fn signed_data(rrsig: &Rrsig, rrset: &Rrset) -> Result<Vec<u8>, String> {
+    let mut rrsig = rrsig.clone();
+    rrsig.signature.clear();
+    let mut data = Record::Rrsig(rrsig.clone()).rdata_wire(".")?;
+
+    let labels = name::labels(&rrset.name)?;
+    let owner = if labels.len() > rrsig.labels as usize {
+        let mut wire = vec![1, b'*'];
+        for label in &labels[labels.len() - rrsig.labels as usize..] {
+            wire.push(label.len() as u8);
+            wire.extend(label.iter().map(u8::to_ascii_lowercase));
+        }
+        wire.push(0);
+        wire
+    } else {
+        name::to_wire(&rrset.name, true)?
+    };
+    let mut rdatas = rrset.rdata.clone();
+    rdatas.sort();
+    for rdata in rdatas {
+        data.extend(&owner);
+        data.extend(rrset.rtype.code().to_be_bytes());
+        data.extend(1u16.to_be_bytes());
+        data.extend(rrsig.original_ttl.to_be_bytes());
+        data.extend((rdata.len() as u16).to_be_bytes());
+        data.extend(rdata);
+    }
+    Ok(data)
+}
+
+/// hashed owner name of RFC 5155 section 5, SHA-1 is the only defined algorithm
+pub(crate) fn nsec3_hash(owner: &str, salt: &[u8], iterations: u16) -> Result<Vec<u8>, String> {
+    let mut hash = Sha1::new()
+        .chain_update(name::to_wire(owner, true)?)
+        .chain_update(salt)
+        .finalize()
+        .to_vec();
+    for _ in 0..iterations {
+        hash = Sha1::new().chain_update(&hash).chain_update(salt).finalize().to_vec();
+    }
+    Ok(hash)
+}
@@ -0,0 +1,134 @@
+/// hex encoding used by digest fields (DS, ZONEMD)
+/// zone files conventionally print digests upper-case
+pub fn hex_encode(data: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    let mut ret = String::with_capacity(data.len() * 2);
+    for b in data {
+        ret.push(DIGITS[(b >> 4) as usize] as char);
+        ret.push(DIGITS[(b & 0x0f) as usize] as char);
+    }
+    ret
+}
+
+/// decode hex string, whitespace between digits is ignored
+/// so digests split across several tokens can be passed as is
+pub fn hex_decode(data: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = data
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("odd number of hex digits in `{data}`"));
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let hi = (pair[0] as char).to_digit(16);
+            let lo = (pair[1] as char).to_digit(16);
+            match (hi, lo) {
+                (Some(hi), Some(lo)) => Ok((hi * 16 + lo) as u8),
+                _ => Err(format!("invalid hex digit in `{data}`")),
+            }
+        })
+        .collect()
+}
+
+/// base64 used by key and signature fields (DNSKEY, RRSIG)
+pub fn base64_encode(data: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
+
+/// decode base64, whitespace is ignored like in zone files
+pub fn base64_decode(data: &str) -> Result<Vec<u8>, String> {
+    use base64::Engine;
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(&data)
+        .map_err(|e| format!("invalid base64 `{data}`: {e}"))
+}
+
+const BASE32HEX: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+/// base32hex without padding, the encoding of NSEC3 hashed owner names (RFC 5155)
+pub fn base32hex_encode(data: &[u8]) -> String {
+    let mut ret = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    for b in data {
+        buffer = (buffer << 8) | *b as u64;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            ret.push(BASE32HEX[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        ret.push(BASE32HEX[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    ret
+}
+
+/// decode base32hex, case-insensitive and without padding
+pub fn base32hex_decode(data: &str) -> Result<Vec<u8>, String> {
+    let mut ret = Vec::with_capacity(data.len() * 5 / 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    for c in data.bytes().filter(|b| !b.is_ascii_whitespace() && *b != b'=') {
+        let value = BASE32HEX
+            .iter()
+            .position(|d| *d == c.to_ascii_uppercase())
+            .ok_or_else(|| format!("invalid base32hex digit in `{data}`"))?;
+        buffer = (buffer << 5) | value as u64;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            ret.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(ret)
+}
+
+/// RRSIG timestamp in the YYYYMMDDHHmmSS presentation form (RFC 4034 section 3.2)
+pub fn dnssec_time_encode(time: u32) -> String {
+    chrono::DateTime::from_timestamp(time as i64, 0)
+        .map_or_else(|| time.to_string(), |t| t.format("%Y%m%d%H%M%S").to_string())
+}
+
+/// parse an RRSIG timestamp, either YYYYMMDDHHmmSS or seconds since the epoch
+pub fn dnssec_time_decode(data: &str) -> Result<u32, String> {
+    if data.len() == 14 && data.bytes().all(|b| b.is_ascii_digit()) {
+        return chrono::NaiveDateTime::parse_from_str(data, "%Y%m%d%H%M%S")
+            .map(|t| t.and_utc().timestamp() as u32)
+            .map_err(|e| format!("invalid RRSIG time `{data}`: {e}"));
+    }
+    data.parse().map_err(|_| format!("invalid RRSIG time `{data}`"))
+}
+
+#[cfg(feature="serde")]
+pub mod hex_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::hex_encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        super::hex_decode(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature="serde")]
+pub mod base64_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::base64_encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        super::base64_decode(&s).map_err(serde::de::Error::custom)
+    }
+}
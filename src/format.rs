@@ -0,0 +1,131 @@
+//! layout of the record lines `generate` writes for a plain `{set}`
+
+use std::io::{self, Write};
+
+use crate::generator::{GenerateOptions, TypeCase, Whitespace};
+
+/// tab stops assumed when aligning with tabs
+const TAB_WIDTH: usize = 8;
+
+/// owner, ttl and class columns, the type and rdata follow
+const TTL: usize = 1;
+const CLASS: usize = 2;
+
+/// write one line per record of a section, `record` gives the `name`, `ttl`
+/// and rdata fields of the record at an index, `None` past the last one
+///
+/// aligned sections are walked twice, once for the column widths,
+/// so only one record is held at a time
+pub(crate) fn write_lines(
+    rtype: &str,
+    record: impl Fn(usize) -> Option<Vec<String>>,
+    options: &GenerateOptions,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let rtype = match options.type_case {
+        TypeCase::Upper => rtype.to_uppercase(),
+        TypeCase::Lower => rtype.to_lowercase(),
+    };
+    let class = if options.explicit_class { "IN" } else { "" };
+    let rtype = rtype.as_str();
+    let rows = || {
+        let mut previous_owner: Option<String> = None;
+        (0..).map_while(&record).map(move |fields| {
+            let repeated = previous_owner.as_ref() == Some(&fields[0]);
+            previous_owner = Some(fields[0].clone());
+            let owner = if repeated && !options.repeat_owner { String::new() } else { fields[0].clone() };
+            let mut row = vec![owner, fields[1].clone(), class.to_string(), rtype.to_string()];
+            row.extend(fields.into_iter().skip(2));
+            row
+        })
+    };
+    let widths = if options.align { Some(widths(rows())) } else { None };
+    for row in rows() {
+        let line = match &widths {
+            Some(widths) => aligned(&row, widths, options.whitespace),
+            None => joined(&row, options.whitespace),
+        };
+        out.write_all(line.as_bytes())?;
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// write the SOA record at the start of `fields` like the records of a set,
+/// the timers follow on their own lines, each with a comment
+pub(crate) fn write_soa(fields: Option<Vec<String>>, options: &GenerateOptions, out: &mut dyn Write) -> io::Result<()> {
+    let Some(fields) = fields else {
+        return Ok(());
+    };
+    let mut header = fields[..4].to_vec();
+    header.push("(".to_owned());
+    write_lines("SOA", |index| (index == 0).then(|| header.clone()), options, out)?;
+    let gap = match options.whitespace {
+        Whitespace::Tabs => "\t ",
+        Whitespace::Spaces => " ",
+    };
+    for (value, comment) in fields[4..].iter().zip(["serial", "refresh", "retry", "expire", "minimum ttl"]) {
+        writeln!(out, "{value}{gap};{comment}")?;
+    }
+    out.write_all(b")\n")
+}
+
+fn separator(whitespace: Whitespace) -> &'static str {
+    match whitespace {
+        Whitespace::Tabs => "\t",
+        Whitespace::Spaces => " ",
+    }
+}
+
+/// a single separator between fields, an empty ttl or class is left out
+fn joined(row: &[String], whitespace: Whitespace) -> String {
+    let separator = separator(whitespace);
+    let mut line = row[0].clone();
+    for (column, field) in row.iter().enumerate().skip(1) {
+        if field.is_empty() && (column == TTL || column == CLASS) {
+            continue;
+        }
+        line.push_str(separator);
+        line.push_str(field);
+    }
+    line
+}
+
+/// width of the widest field of every column
+fn widths(rows: impl Iterator<Item = Vec<String>>) -> Vec<usize> {
+    let mut ret = Vec::new();
+    for row in rows {
+        ret.resize(ret.len().max(row.len()), 0);
+        for (column, field) in row.iter().enumerate() {
+            ret[column] = ret[column].max(field.chars().count());
+        }
+    }
+    ret
+}
+
+/// every column padded to the widest field of the section,
+/// columns empty on every row are left out
+fn aligned(row: &[String], widths: &[usize], whitespace: Whitespace) -> String {
+    let mut line = String::new();
+    let last = row.len() - 1;
+    for (column, field) in row.iter().enumerate() {
+        if widths[column] == 0 && column != 0 {
+            continue;
+        }
+        line.push_str(field);
+        if column < last {
+            pad(&mut line, field.chars().count(), widths[column], whitespace);
+        }
+    }
+    line
+}
+
+fn pad(line: &mut String, len: usize, width: usize, whitespace: Whitespace) {
+    match whitespace {
+        Whitespace::Tabs => {
+            let tabs = width / TAB_WIDTH + 1 - len / TAB_WIDTH;
+            line.extend(std::iter::repeat_n('\t', tabs));
+        }
+        Whitespace::Spaces => line.extend(std::iter::repeat_n(' ', width - len + 1)),
+    }
+}
@@ -1,228 +1,475 @@
-use chrono::{DateTime, Utc};
-use regex::Regex;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::borrow::Cow;
+use std::io::{self, Write};
 
-// static REX_PATTERN: LazyCell<Regex> = LazyCell::new(|| {
-//     let re = Regex::new(r"\n{2,}").unwrap();
-//     re
-// });
+use chrono::{DateTime, Utc};
 
+use crate::dialect::Dialect;
 use crate::dns_structs::*;
+use crate::encoding::{base32hex_encode, base64_encode, dnssec_time_encode, hex_encode};
+use crate::format;
+use crate::name;
+use crate::record::{self, Record};
+use crate::serial::SerialBump;
+use crate::template::{Data, Schema, SetSchema, Template};
 
-pub fn generate(re: &Regex,options: &DnsRecord, template: Option<&str>) -> String {
-    let mut template = template.unwrap_or(DEFAULT_TEMPLATE).to_string();
-
-    template = process_origin(options.origin.as_deref(), template);
-    template = process_ttl(options.ttl, template);
-    template = process_soa(options.soa.as_ref(), template);
-    template = process_ns(options.ns.as_ref().unwrap_or(&vec![]), template);
-    template = process_a(options.a.as_ref().unwrap_or(&vec![]), template);
-    template = process_aaaa(options.aaaa.as_ref().unwrap_or(&vec![]), template);
-    template = process_cname(options.cname.as_ref().unwrap_or(&vec![]), template);
-    template = process_mx(options.mx.as_ref().unwrap_or(&vec![]), template);
-    template = process_ptr(options.ptr.as_ref().unwrap_or(&vec![]), template);
-    template = process_txt(options.txt.as_ref().unwrap_or(&vec![]), template);
-    template = process_srv(options.srv.as_ref().unwrap_or(&vec![]), template);
-    template = process_spf(options.spf.as_ref().unwrap_or(&vec![]), template);
-    template = process_caa(options.caa.as_ref().unwrap_or(&vec![]), template);
-    template = process_ds(options.ds.as_ref().unwrap_or(&vec![]), template);
-    template = process_values(options, template);
-    
-    re.replace_all(&template, "\n\n").to_string()
-}
-
-fn process_origin(data: Option<&str>, template: String) -> String {
-    let ret = if let Some(d) = data {
-        format!("$ORIGIN {d}")
-    } else {
-        "".to_string()
-    };
-    template.replace("{$origin}", &ret)
+/// placeholders `generate` fills in, every record set has `name` and `ttl` first
+const SCHEMA: Schema = Schema {
+    globals: &[
+        "zone", "datetime", "time", "exported", "$origin", "$ttl", "origin", "name", "ttl", "mname", "rname", "serial",
+        "refresh", "retry", "expire", "minimum",
+    ],
+    sets: &[
+        SetSchema {
+            name: "soa",
+            fields: &["name", "ttl", "mname", "rname", "serial", "refresh", "retry", "expire", "minimum"],
+        },
+        SetSchema { name: "ns", fields: &["name", "ttl", "host"] },
+        SetSchema { name: "a", fields: &["name", "ttl", "ip"] },
+        SetSchema { name: "aaaa", fields: &["name", "ttl", "ip"] },
+        SetSchema { name: "cname", fields: &["name", "ttl", "alias"] },
+        SetSchema { name: "mx", fields: &["name", "ttl", "preference", "host"] },
+        SetSchema { name: "ptr", fields: &["name", "ttl", "host"] },
+        SetSchema { name: "txt", fields: &["name", "ttl", "txt"] },
+        SetSchema { name: "srv", fields: &["name", "ttl", "priority", "weight", "port", "target"] },
+        SetSchema { name: "spf", fields: &["name", "ttl", "data"] },
+        SetSchema { name: "caa", fields: &["name", "ttl", "flags", "tag", "value"] },
+        SetSchema { name: "ds", fields: &["name", "ttl", "key_tag", "algorithm", "digest_type", "digest"] },
+        SetSchema { name: "dnskey", fields: &["name", "ttl", "flags", "protocol", "algorithm", "public_key"] },
+        SetSchema { name: "nsec3param", fields: &["name", "ttl", "hash_algorithm", "flags", "iterations", "salt"] },
+        SetSchema { name: "nsec", fields: &["name", "ttl", "next_domain", "types"] },
+        SetSchema {
+            name: "nsec3",
+            fields: &["name", "ttl", "hash_algorithm", "flags", "iterations", "salt", "next_hashed", "types"],
+        },
+        SetSchema {
+            name: "rrsig",
+            fields: &[
+                "name", "ttl", "type_covered", "algorithm", "labels", "original_ttl", "expiration", "inception", "key_tag",
+                "signer_name", "signature",
+            ],
+        },
+        SetSchema { name: "zonemd", fields: &["name", "ttl", "serial", "scheme", "hash_algorithm", "digest"] },
+    ],
+};
+
+/// whitespace between the fields of a record
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Whitespace {
+    #[default]
+    Tabs,
+    Spaces,
+}
+
+/// case of the type mnemonics like `A` or `a`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum TypeCase {
+    #[default]
+    Upper,
+    Lower,
+}
+
+/// how owner names and in-zone targets are written
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum NameStyle {
+    /// as they are in the zone
+    #[default]
+    AsIs,
+    /// relative to the origin, the apex is `@`
+    Relative,
+    /// fully qualified
+    Absolute,
 }
 
-fn process_ttl(data: Option<u32>, template: String) -> String {
-    let ret = if let Some(d) = data {
-        format!("$TTL {d}")
-    } else {
-        "".to_string()
+/// layout of the records `generate` writes for a plain `{set}` placeholder
+///
+/// the default is one tab between fields, like `DnsZonefile::generate`
+#[derive(Debug, PartialEq, Clone)]
+pub struct GenerateOptions {
+    /// pad the fields of every section to the same column
+    pub align: bool,
+    pub whitespace: Whitespace,
+    pub type_case: TypeCase,
+    /// write the owner name of consecutive records with the same owner,
+    /// otherwise the line starts with whitespace
+    pub repeat_owner: bool,
+    /// write the `IN` class
+    pub explicit_class: bool,
+    /// time `{datetime}`, `{time}` and a `{time}` serial are filled in with,
+    /// the current time when not set
+    pub now: Option<DateTime<Utc>>,
+    /// write the `; Exported` line of the header, without it and with `now`
+    /// set the output only depends on the zone
+    pub exported_header: bool,
+    /// bump the SOA serial when the zone changed since the previous version
+    pub bump_serial: Option<SerialBump>,
+    /// rewrite owner names and the CNAME, MX, SRV and NS targets
+    /// inside the zone, names outside the zone are left alone
+    pub names: NameStyle,
+    /// write the zone as `DnsRecord::canonicalize` leaves it, so the same
+    /// records give the same output whatever order they came in
+    pub canonical: bool,
+    /// write TXT and SPF data split into several character-strings
+    /// in parentheses, one string per line
+    pub multiline_txt: bool,
+    /// server the zone file is written for
+    pub dialect: Dialect,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            align: false,
+            whitespace: Whitespace::Tabs,
+            type_case: TypeCase::Upper,
+            repeat_owner: true,
+            explicit_class: true,
+            now: None,
+            exported_header: true,
+            bump_serial: None,
+            names: NameStyle::AsIs,
+            canonical: false,
+            multiline_txt: false,
+            dialect: Dialect::Bind,
+        }
+    }
+}
+
+/// write `zone` with `template` to `out`, records are formatted one at a time
+/// as the template asks for them
+///
+/// records the dialect can not represent are left out when `lenient`
+/// and returned as warnings, otherwise the first one is an error
+pub fn write(
+    out: &mut dyn Write,
+    zone: &DnsRecord,
+    template: Option<&str>,
+    format: &GenerateOptions,
+    lenient: bool,
+) -> io::Result<Vec<GenerateWarning>> {
+    let template = Template::parse(template.unwrap_or(DEFAULT_TEMPLATE), &SCHEMA)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let now = format.now.unwrap_or_else(Utc::now);
+    let time = now.timestamp();
+    let (zone, warnings) = prepare(zone, format, now).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(warning) = warnings.first().filter(|_| !lenient) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, warning.to_string()));
+    }
+    let zone = zone.as_ref();
+
+    let origin = zone.origin.as_deref().unwrap_or_else(|| {
+        zone.soa.as_ref().map_or("", |s| &s.name)
+    });
+    let mut globals = vec![
+        origin.to_string(),
+        now.to_rfc3339(),
+        time.to_string(),
+        if format.exported_header { now.to_rfc3339() } else { String::new() },
+        zone.origin.as_ref().map_or(String::new(), |o| format!("$ORIGIN {o}")),
+        zone.ttl.map_or(String::new(), |t| format!("$TTL {t}")),
+        zone.origin.clone().unwrap_or_default(),
+    ];
+    globals.extend(soa_values(zone.soa.as_ref(), time));
+
+    let data = ZoneData { zone, globals, format };
+    let mut out = CollapseNewlines { out, newlines: 0 };
+    template.render(&data, &mut out)?;
+    out.flush()?;
+    Ok(warnings)
+}
+
+/// the zone after the canonical order, serial bump, name style and dialect
+/// of `format`, borrowed when none of them apply
+fn prepare<'a>(
+    zone: &'a DnsRecord,
+    format: &GenerateOptions,
+    now: DateTime<Utc>,
+) -> Result<(Cow<'a, DnsRecord>, Vec<GenerateWarning>), String> {
+    let unchanged = !format.canonical && format.bump_serial.is_none() && format.names == NameStyle::AsIs;
+    if unchanged && format.dialect == Dialect::Bind {
+        return Ok((Cow::Borrowed(zone), Vec::new()));
+    }
+    let (mut zone, warnings) = format.dialect.adapt(zone)?;
+    if format.canonical {
+        zone.canonicalize()?;
+    }
+    if let Some(bump) = &format.bump_serial {
+        zone.soa = bump.soa(&zone, now)?;
+    }
+    Ok((Cow::Owned(normalize_names(zone, format.names)), warnings))
+}
+
+struct ZoneData<'a> {
+    zone: &'a DnsRecord,
+    globals: Vec<String>,
+    format: &'a GenerateOptions,
+}
+
+impl Data for ZoneData<'_> {
+    fn global(&self, global: usize) -> &str {
+        &self.globals[global]
+    }
+
+    fn record(&self, set: usize, index: usize) -> Option<Vec<String>> {
+        let zone = self.zone;
+        let multiline = self.format.multiline_txt;
+        match SCHEMA.sets[set].name {
+            // the SOA globals follow the seven others
+            "soa" => zone.soa.as_ref().filter(|_| index == 0).map(|_| self.globals[7..].to_vec()),
+            "ns" => record(&zone.ns, index, |v| (&v.name, v.ttl, vec![v.host.clone()])),
+            "a" => record(&zone.a, index, |v| (&v.name, v.ttl, vec![v.ip.clone()])),
+            "aaaa" => record(&zone.aaaa, index, |v| (&v.name, v.ttl, vec![v.ip.clone()])),
+            "cname" => record(&zone.cname, index, |v| (&v.name, v.ttl, vec![v.alias.clone()])),
+            "mx" => record(&zone.mx, index, |v| (&v.name, v.ttl, vec![v.preference.to_string(), v.host.clone()])),
+            "ptr" => record(&zone.ptr, index, |v| (&v.name, v.ttl, vec![v.host.clone()])),
+            "txt" => record(&zone.txt, index, |v| (&v.name, v.ttl, vec![character_strings(&v.txt, multiline)])),
+            "srv" => record(&zone.srv, index, |v| {
+                (&v.name, v.ttl, vec![v.priority.to_string(), v.weight.to_string(), v.port.to_string(), v.target.clone()])
+            }),
+            "spf" => record(&zone.spf, index, |v| (&v.name, v.ttl, vec![character_strings(&v.data, multiline)])),
+            "caa" => record(&zone.caa, index, |v| {
+                (&v.name, v.ttl, vec![v.flags.to_string(), v.tag.clone(), record::quote_character_string(v.value.as_bytes())])
+            }),
+            "ds" => record(&zone.ds, index, |v| {
+                if let Some(raw) = &v.raw {
+                    return (&v.name, v.ttl, vec![raw.rdata.clone()]);
+                }
+                (
+                    &v.name,
+                    v.ttl,
+                    vec![
+                        v.key_tag.to_string(),
+                        u8::from(v.algorithm).to_string(),
+                        u8::from(v.digest_type).to_string(),
+                        hex_encode(&v.digest),
+                    ],
+                )
+            }),
+            "dnskey" => record(&zone.dnskey, index, |v| {
+                (
+                    &v.name,
+                    v.ttl,
+                    vec![
+                        v.flags.to_string(),
+                        v.protocol.to_string(),
+                        u8::from(v.algorithm).to_string(),
+                        base64_encode(&v.public_key),
+                    ],
+                )
+            }),
+            "nsec3param" => record(&zone.nsec3param, index, |v| {
+                (
+                    &v.name,
+                    v.ttl,
+                    vec![v.hash_algorithm.to_string(), v.flags.to_string(), v.iterations.to_string(), salt_string(&v.salt)],
+                )
+            }),
+            "nsec" => record(&zone.nsec, index, |v| (&v.name, v.ttl, vec![v.next_domain.clone(), types_list(&v.types)])),
+            "nsec3" => record(&zone.nsec3, index, |v| {
+                (
+                    &v.name,
+                    v.ttl,
+                    vec![
+                        v.hash_algorithm.to_string(),
+                        v.flags.to_string(),
+                        v.iterations.to_string(),
+                        salt_string(&v.salt),
+                        base32hex_encode(&v.next_hashed),
+                        types_list(&v.types),
+                    ],
+                )
+            }),
+            "rrsig" => record(&zone.rrsig, index, |v| {
+                (
+                    &v.name,
+                    v.ttl,
+                    vec![
+                        v.type_covered.to_string(),
+                        u8::from(v.algorithm).to_string(),
+                        v.labels.to_string(),
+                        v.original_ttl.to_string(),
+                        dnssec_time_encode(v.expiration),
+                        dnssec_time_encode(v.inception),
+                        v.key_tag.to_string(),
+                        v.signer_name.clone(),
+                        base64_encode(&v.signature),
+                    ],
+                )
+            }),
+            "zonemd" => record(&zone.zonemd, index, |v| {
+                (
+                    &v.name,
+                    v.ttl,
+                    vec![v.serial.to_string(), v.scheme.to_string(), v.hash_algorithm.to_string(), hex_encode(&v.digest)],
+                )
+            }),
+            _ => None,
+        }
+    }
+
+    fn write_lines(&self, set: usize, out: &mut dyn Write) -> io::Result<()> {
+        if SCHEMA.sets[set].name == "soa" {
+            return format::write_soa(self.record(set, 0), self.format, out);
+        }
+        format::write_lines(SCHEMA.sets[set].name, |index| self.record(set, index), self.format, out)
+    }
+
+    fn origin(&self) -> &str {
+        &self.globals[0]
+    }
+}
+
+/// lets at most two newlines follow each other, so sections the template
+/// leaves empty do not pile up blank lines
+struct CollapseNewlines<'a> {
+    out: &'a mut dyn Write,
+    newlines: usize,
+}
+
+impl Write for CollapseNewlines<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut start = 0;
+        for (i, b) in buf.iter().enumerate() {
+            if *b != b'\n' {
+                self.newlines = 0;
+                continue;
+            }
+            self.newlines += 1;
+            if self.newlines > 2 {
+                self.out.write_all(&buf[start..i])?;
+                start = i + 1;
+            }
+        }
+        self.out.write_all(&buf[start..])?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// zone with the owner names and in-zone targets written in `style`,
+/// zones without an origin are returned as they are
+pub(crate) fn normalize_names(mut zone: DnsRecord, style: NameStyle) -> DnsRecord {
+    let origin = zone.zone_origin();
+    if style == NameStyle::AsIs || origin == "." {
+        return zone;
+    }
+    let rewrite = |name: &mut String| {
+        let absolute = name::fqdn(name, &origin);
+        *name = match style {
+            NameStyle::Relative => name::relative(&absolute, &origin),
+            _ => absolute,
+        };
     };
-    template.replace("{$ttl}", &ret)
-}
-
-fn process_soa(data: Option<&Soa>, mut template: String) -> String {
-    if let Some(soa) = data {
-        let name = if soa.name.is_empty() { "@" } else { &soa.name };
-        let ttl = soa.ttl.map_or("".to_string(), |t| t.to_string());
-        template = template.replace("{name}", &format!("{}\t", name));
-        template = template.replace("{ttl}", &format!("{}\t", ttl));
-        template = template.replace("{mname}", &format!("{}\t", soa.mname));
-        template = template.replace("{rname}", &format!("{}\t", soa.rname));
-        template = template.replace("{serial}", &format!("{}\t", soa.serial));
-        template = template.replace("{refresh}", &format!("{}\t", soa.refresh));
-        template = template.replace("{retry}", &format!("{}\t", soa.retry));
-        template = template.replace("{expire}", &format!("{}\t", soa.expire));
-        template = template.replace("{minimum}", &format!("{}\t", soa.minimum));
+    fn each<T>(data: &mut Option<Vec<T>>, f: impl Fn(&mut T)) {
+        data.iter_mut().flatten().for_each(f);
     }
-    template
-}
-
-fn process_ns(data: &[Ns], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!("{}\t{}IN\tNS\t{}\n", name, ttl, value.host)
-        })
-        .collect();
-    template.replace("{ns}", &ret)
-}
-
-fn process_a(data: &[A], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!("{}\t{}IN\tA\t{}\n", name, ttl, value.ip)
-        })
-        .collect();
-    template.replace("{a}", &ret)
-}
-
-fn process_aaaa(data: &[Aaaa], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!("{}\t{}IN\tAAAA\t{}\n", name, ttl, value.ip)
-        })
-        .collect();
-    template.replace("{aaaa}", &ret)
-}
-
-fn process_cname(data: &[Cname], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!("{}\t{}IN\tCNAME\t{}\n", name, ttl, value.alias)
-        })
-        .collect();
-    template.replace("{cname}", &ret)
-}
-
-fn process_mx(data: &[Mx], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!(
-                "{}\t{}IN\tMX\t{}\t{}\n",
-                name, ttl, value.preference, value.host
-            )
-        })
-        .collect();
-    template.replace("{mx}", &ret)
-}
-
-fn process_ptr(data: &[Ptr], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!("{}\t{}IN\tPTR\t{}\n", name, ttl, value.host)
-        })
-        .collect();
-    template.replace("{ptr}", &ret)
-}
-
-fn process_txt(data: &[Txt], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!("{}\t{}IN\tTXT\t{}\n", name, ttl, value.txt)
-        })
-        .collect();
-    template.replace("{txt}", &ret)
-}
-
-fn process_srv(data: &[Srv], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!(
-                "{}\t{}IN\tSRV\t{}\t{}\t{}\t{}\n",
-                name, ttl, value.priority, value.weight, value.port, value.target
-            )
-        })
-        .collect();
-    template.replace("{srv}", &ret)
-}
-
-fn process_spf(data: &[Spf], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!("{}\t{}IN\tSPF\t{}\n", name, ttl, value.data)
-        })
-        .collect();
-    template.replace("{spf}", &ret)
-}
-
-fn process_caa(data: &[Caa], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!(
-                "{}\t{}IN\tCAA\t{}\t{}\t{}\n",
-                name, ttl, value.flags, value.tag, value.value
-            )
-        })
-        .collect();
-    template.replace("{caa}", &ret)
-}
-
-fn process_ds(data: &[Ds], template: String) -> String {
-    let ret: String = data
-        .iter()
-        .map(|value| {
-            let name = if value.name.is_empty() { "@" } else { &value.name };
-            let ttl = value.ttl.map_or("".to_string(), |t| format!("{}\t", t));
-            format!(
-                "{}\t{}IN\tDS\t{}\t{}\t{}\t{}\n",
-                name, ttl, value.key_tag, value.algorithm, value.digest_type, value.digest
-            )
-        })
-        .collect();
-    template.replace("{ds}", &ret)
-}
-
-fn process_values(options: &DnsRecord, template: String) -> String {
-    let zone = options.origin.as_deref().unwrap_or_else(|| {
-        options.soa.as_ref().map_or("", |s| &s.name)
+    if let Some(soa) = zone.soa.as_mut() {
+        rewrite(&mut soa.name);
+    }
+    each(&mut zone.ns, |r| {
+        rewrite(&mut r.name);
+        rewrite(&mut r.host);
+    });
+    each(&mut zone.cname, |r| {
+        rewrite(&mut r.name);
+        rewrite(&mut r.alias);
+    });
+    each(&mut zone.mx, |r| {
+        rewrite(&mut r.name);
+        rewrite(&mut r.host);
     });
+    each(&mut zone.srv, |r| {
+        rewrite(&mut r.name);
+        rewrite(&mut r.target);
+    });
+    each(&mut zone.a, |r| rewrite(&mut r.name));
+    each(&mut zone.aaaa, |r| rewrite(&mut r.name));
+    each(&mut zone.ptr, |r| rewrite(&mut r.name));
+    each(&mut zone.txt, |r| rewrite(&mut r.name));
+    each(&mut zone.spf, |r| rewrite(&mut r.name));
+    each(&mut zone.caa, |r| rewrite(&mut r.name));
+    each(&mut zone.ds, |r| rewrite(&mut r.name));
+    each(&mut zone.dnskey, |r| rewrite(&mut r.name));
+    each(&mut zone.nsec3param, |r| rewrite(&mut r.name));
+    each(&mut zone.nsec, |r| rewrite(&mut r.name));
+    each(&mut zone.nsec3, |r| rewrite(&mut r.name));
+    each(&mut zone.rrsig, |r| rewrite(&mut r.name));
+    each(&mut zone.zonemd, |r| rewrite(&mut r.name));
+    zone
+}
+
+/// one record in presentation format, `name ttl IN TYPE rdata` separated by tabs
+pub(crate) fn record_line(record: &Record) -> String {
+    let format = GenerateOptions { explicit_class: true, ..Default::default() };
+    let rtype = record.record_type().to_string();
+    let fields = match record {
+        Record::Soa(soa) => Some(soa_values(Some(soa), 0)),
+        _ => {
+            let mut zone = DnsRecord::default();
+            zone.push(record.clone());
+            let data = ZoneData { zone: &zone, globals: Vec::new(), format: &format };
+            SCHEMA.sets.iter().position(|s| s.name.eq_ignore_ascii_case(&rtype)).and_then(|set| data.record(set, 0))
+        }
+    };
+    let mut out = Vec::new();
+    // writing to a Vec does not fail
+    let _ = format::write_lines(&rtype, |index| fields.clone().filter(|_| index == 0), &format, &mut out);
+    String::from_utf8_lossy(&out).trim_end().to_string()
+}
 
-    let now: DateTime<Utc> = Utc::now();
-    let time = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+/// SOA globals in schema order, all empty without a SOA record
+fn soa_values(data: Option<&Soa>, time: i64) -> Vec<String> {
+    let Some(soa) = data else {
+        return vec![String::new(); 9];
+    };
+    let name = if soa.name.is_empty() { "@" } else { &soa.name };
+    // a serial of `{time}` is filled in with the unix time of `now`
+    let serial = match &soa.serial {
+        Serial::String(s) if s == "{time}" => time.to_string(),
+        serial => serial.to_string(),
+    };
+    vec![
+        name.to_string(),
+        soa.ttl.map_or(String::new(), |t| t.to_string()),
+        soa.mname.clone(),
+        soa.rname.clone(),
+        serial,
+        soa.refresh.to_string(),
+        soa.retry.to_string(),
+        soa.expire.to_string(),
+        soa.minimum.to_string(),
+    ]
+}
+
+/// template fields of the record at `index` of a set, `name` defaults to `@`
+fn record<T>(
+    data: &Option<Vec<T>>,
+    index: usize,
+    fields: impl Fn(&T) -> (&String, Option<u32>, Vec<String>),
+) -> Option<Vec<String>> {
+    let (name, ttl, rdata) = fields(data.as_ref()?.get(index)?);
+    let name = if name.is_empty() { "@" } else { name };
+    let mut ret = vec![name.to_string(), ttl.map_or(String::new(), |t| t.to_string())];
+    ret.extend(rdata);
+    Some(ret)
+}
+
+/// TXT or SPF data with strings longer than 255 octets split,
+/// data that is not valid presentation format is written as it is
+fn character_strings(data: &str, multiline: bool) -> String {
+    match record::character_strings(data) {
+        Ok(strings) if multiline && strings.len() > 1 => format!("( {} )", strings.join("\n\t\t")),
+        Ok(strings) => strings.join(" "),
+        Err(_) => data.to_string(),
+    }
+}
+
+fn types_list(types: &[RecordType]) -> String {
+    types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(" ")
+}
 
-    template
-        .replace("{zone}", zone)
-        .replace("{datetime}", &now.to_rfc3339())
-        .replace("{time}", &time.to_string())
+fn salt_string(salt: &[u8]) -> String {
+    if salt.is_empty() { "-".to_string() } else { hex_encode(salt) }
 }
@@ -1,15 +1,64 @@
+mod caa;
+mod check;
+mod delegation;
+mod diff;
+mod dialect;
 mod dns_structs;
+mod dnskey;
+#[cfg(feature="dnssec")]
+mod dnssec;
+mod encoding;
+mod format;
 mod generator;
+mod lint;
+mod mail;
+mod name;
 mod parser;
+mod patch;
+mod record;
+mod reverse;
+mod serial;
+#[cfg(feature="dnssec")]
+mod signing;
+mod spf;
+mod template;
+mod update;
+#[cfg(feature="dnssec")]
+mod validation;
+mod zonemd;
 
+pub use caa::{CaaIssuer, CaaProperty, CaaTag};
+pub use check::{Finding, FindingKind, Severity, ValidationReport};
+pub use delegation::Delegation;
+pub use diff::{RecordChange, RrsetDiff, ZoneDiff};
+pub use dialect::Dialect;
 pub use dns_structs::*;
+pub use generator::{GenerateOptions, NameStyle, TypeCase, Whitespace};
+pub use lint::{
+    builtin_rules, HostnameSyntax, LintConfig, LintFinding, LintRule, Linter, MinTtl, NoWildcardMx, OptionValue, RequireCaa,
+    RuleConfig, Suppressions, Violation,
+};
+pub use mail::{
+    Alignment, Dkim, DkimKeyType, Dmarc, DmarcPolicy, MailEntry, MailPolicy, MtaSts, TlsRpt, DKIM_MIN_RSA_BITS,
+};
+pub use patch::{PatchOperation, Precondition, ZonePatch};
+pub use record::Record;
+pub use reverse::ReverseNetwork;
+pub use serial::{SerialBump, SerialStrategy};
+#[cfg(feature="dnssec")]
+pub use signing::{Denial, SigningKey, SigningOptions};
+pub use spf::{is_spf, Directive, Mechanism, Modifier, Qualifier, SpfEntry, SpfRecord, SpfReport, Term, SPF_LOOKUP_LIMIT};
+pub use update::{DnsUpdate, Prerequisite, TsigKey};
+#[cfg(feature="dnssec")]
+pub use validation::{DnssecReport, RrsetResult, RrsetStatus};
+pub use zonemd::{ZonemdHashAlgorithm, ZonemdScheme};
 use regex::Regex;
+use std::io::Write;
 
 #[derive(Debug, Clone)]
 struct ContextRegex {
     parser: Regex,
     parser_soa: Regex,
-    generator: Regex
 }
 
 #[derive(Debug, Clone)]
@@ -23,6 +72,7 @@ pub struct DnsZonefile {
 /// which will create Regex resource once using `DnsZonefile::default()`
 /// if you want to use with serde enable feature `serde`
 /// there is also `paperclip` feature use to expose the struct to openapi specs
+/// and `dnssec` feature to sign zones with `DnsRecord::sign`
 /// ```rust
 /// use dns_zonefile::{DnsRecord, DnsZonefile};
 ///
@@ -57,31 +107,96 @@ pub struct DnsZonefile {
 /// let Ok(zone_data) = dns.parse(zonefile_example) else { return;};
 /// println!("{}", serde_json::to_string_pretty(&zone_data).unwrap());
 /// 
-/// let zonefile = dns.generate(&zone_data, None);
+/// let zonefile = dns.generate(&zone_data, None).unwrap();
 /// println!("{zonefile}");
 /// ```
 impl DnsZonefile {
     /// generate Zonefile string from DnsRecord data struct like so
-    /// 
-    pub fn generate(&self, dns_zone: &DnsRecord, template: Option<&str>) -> String {
-        generator::generate(&self.ctx.generator, dns_zone, template)
+    ///
+    /// `template` defaults to `DEFAULT_TEMPLATE`, placeholders are
+    /// `{zone}`, `{datetime}`, `{time}`, `{exported}`, `{$origin}`, `{$ttl}`, the SOA fields
+    /// and one record set per record type like `{soa}`, `{a}` or `{#each a}{name} {ip}{/each}`
+    ///
+    /// THIS function **return** error for templates with unknown placeholders
+    pub fn generate(&self, dns_zone: &DnsRecord, template: Option<&str>) -> Result<String, String> {
+        self.generate_with(dns_zone, template, &GenerateOptions::default())
+    }
+    /// generate like `generate` with the record layout of `options`,
+    /// like column alignment or lower case type mnemonics
+    ///
+    /// THIS function **return** error for records the `dialect` of `options`
+    /// can not represent, see `generate_lenient`
+    pub fn generate_with(&self, dns_zone: &DnsRecord, template: Option<&str>, options: &GenerateOptions) -> Result<String, String> {
+        let mut out = Vec::new();
+        generator::write(&mut out, dns_zone, template, options, false).map_err(|e| e.to_string())?;
+        String::from_utf8(out).map_err(|e| e.to_string())
+    }
+    /// generate like `generate_with` but leave out the records the `dialect`
+    /// of `options` can not represent
+    ///
+    /// each left out record is reported as a `GenerateWarning`,
+    /// handy to write one zone for servers of different kinds
+    pub fn generate_lenient(
+        &self,
+        dns_zone: &DnsRecord,
+        template: Option<&str>,
+        options: &GenerateOptions,
+    ) -> Result<(String, Vec<GenerateWarning>), String> {
+        let mut out = Vec::new();
+        let warnings = generator::write(&mut out, dns_zone, template, options, true).map_err(|e| e.to_string())?;
+        Ok((String::from_utf8(out).map_err(|e| e.to_string())?, warnings))
+    }
+    /// write the zone with `DEFAULT_TEMPLATE` straight to `out`
+    ///
+    /// records are formatted one at a time, so large zones are never held
+    /// in memory as text. wrap files in a `BufWriter`
+    pub fn write_to(&self, dns_zone: &DnsRecord, out: &mut impl Write, options: &GenerateOptions) -> std::io::Result<()> {
+        generator::write(out, dns_zone, None, options, false).map(|_| ())
+    }
+    /// write like `write_to` with `template`, an invalid template is an
+    /// `InvalidInput` error before anything is written
+    pub fn write_template_to(&self, dns_zone: &DnsRecord, template: &str, out: &mut impl Write, options: &GenerateOptions) -> std::io::Result<()> {
+        generator::write(out, dns_zone, Some(template), options, false).map(|_| ())
     }
     /// parse data from zonfile to DnsRecord struct
     ///
     /// then can convert into json later using serde
     ///
     /// THIS function **return** error for empty zone data rather then all none!
+    /// and for records it can not represent, like malformed DS records
     pub fn parse(&self, data: &str) -> Result<DnsRecord, String> {
+        let (zone, _) = parser::parse(&self.ctx.parser, &self.ctx.parser_soa, data)?;
+        Ok(zone)
+    }
+    /// parse like `parse` but refuse zones with records it can not represent
+    pub fn parse_strict(&self, data: &str) -> Result<DnsRecord, String> {
+        let (zone, warnings) = parser::parse(&self.ctx.parser, &self.ctx.parser_soa, data)?;
+        if let Some(warning) = warnings.first() {
+            return Err(warning.to_string());
+        }
+        Ok(zone)
+    }
+    /// parse like `parse` and report records it can not represent
+    ///
+    /// malformed DS records are kept as written, other malformed DNSSEC
+    /// records are skipped, each one is returned as a `ParseWarning`
+    pub fn parse_lenient(&self, data: &str) -> Result<(DnsRecord, Vec<ParseWarning>), String> {
         parser::parse(&self.ctx.parser, &self.ctx.parser_soa, data)
     }
+    /// parse `data` and run `linter` over it, findings silenced by
+    /// `; lint:ignore` comments in the zone file are left out
+    pub fn lint(&self, data: &str, linter: &Linter) -> Result<Vec<LintFinding>, String> {
+        let zone = self.parse(data)?;
+        let suppressions = Suppressions::from_zonefile(data, &zone.zone_origin());
+        linter.lint_suppressed(&zone, &suppressions)
+    }
 }
 
 impl Default for DnsZonefile {
     fn default() -> Self {
         let parser = Regex::new(r"\s+").unwrap();
-        let generator = Regex::new(r"\n{2,}").expect("valid pattern");
         let parser_soa = Regex::new(r"(?is)(SOA\s+.*?\s*\([\s\S]*?\))").expect("valid pattern");
-        Self { ctx: ContextRegex { parser, parser_soa, generator } }
+        Self { ctx: ContextRegex { parser, parser_soa } }
     }
 }
 
@@ -89,7 +204,7 @@ impl Default for DnsZonefile {
 
 #[cfg(test)]
 mod tests {
-    use super::{DnsZonefile, DnsRecord};
+    use super::{A, Caa, CaaIssuer, CaaProperty, CaaTag, Dialect, DigestType, Directive, Dkim, DkimKeyType, Dmarc, DmarcPolicy, DnsRecord, DnssecAlgorithm, DnsZonefile, FindingKind, GenerateOptions, LintConfig, Linter, MailPolicy, Mechanism, MtaSts, NameStyle, PatchOperation, Precondition, Ptr, Record, RecordChange, RecordType, ReverseNetwork, RuleConfig, Serial, SerialBump, SerialStrategy, Severity, SpfRecord, Term, TlsRpt, TsigKey, Txt, TypeCase, Whitespace, ZoneDiff, ZonemdHashAlgorithm, ZonemdScheme, ZonePatch};
     use std::fs;
 
     fn get_forward_zone_json() -> DnsRecord {
@@ -102,7 +217,7 @@ mod tests {
     fn test_generate_global_info() {
         let dns_zonefile = DnsZonefile::default();
         let json = get_forward_zone_json();
-        let generated = dns_zonefile.generate(&json, None);
+        let generated = dns_zonefile.generate(&json, None).unwrap();
         assert!(generated.contains("$ORIGIN MYDOMAIN.COM."));
         assert!(generated.contains("$TTL 3600"));
         assert!(generated.contains("IN\tSOA\tNS1.NAMESERVER.NET.\tHOSTMASTER.MYDOMAIN.COM."));
@@ -138,8 +253,7 @@ mod tests {
         assert!(generated.contains(r#"@	IN	CAA	0	issue	"ca.example.net; account=230123""#));
         assert!(generated.contains(r#"@	IN	CAA	0	iodef	"mailto:security@example.com""#));
         assert!(generated.contains(r#"@	IN	CAA	0	iodef	"http://iodef.example.com/""#));
-        assert!(generated.contains("secure.example.\tIN\tDS\ttag=12345\talg=3\tdigest_type=1\t<foofoo>"));
-        assert!(generated.contains(r#"secure.example.	IN	DS	tag=12345	alg=3	digest_type=1	"<foofoo>""#));
+        assert!(generated.contains("secure.example.\tIN\tDS\t60485\t5\t1\t2BB183AF5F22588179A53B0A98631FAD1A292118"));
     }
 
 
@@ -160,13 +274,898 @@ mod tests {
     fn test_generate_forward_zone() {
         let json_data = get_expected_forward_zone_json();
         let dns_zonefile = DnsZonefile::default();
-        let generated_text = dns_zonefile.generate(&json_data, None);
+        let generated_text = dns_zonefile.generate(&json_data, None).unwrap();
         
         assert!(generated_text.contains("$ORIGIN MYDOMAIN.COM."));
         assert!(generated_text.contains("IN\tSOA\tNS1.NAMESERVER.NET."));
         assert!(generated_text.contains("tst\t300\tIN\tA\t101.228.10.127"));
     }
 
+    #[test]
+    fn test_generate_custom_template() {
+        let dns_zonefile = DnsZonefile::default();
+        let zone = dns_zonefile
+            .parse("$ORIGIN example.com.\nwww.example.com. IN A 192.0.2.1\nmail 300 IN A 192.0.2.2\n@ IN TXT \"{ns} {serial}\"\n")
+            .unwrap();
+        let template = "{#each a}{name|relative|pad:6}{#if ttl}{ttl} {/if}A {ip}\n{/each}{#if mx}; MX\n{else}; no MX\n{/if}{txt}";
+        let generated = dns_zonefile.generate(&zone, Some(template)).unwrap();
+        assert_eq!(generated, "www   A 192.0.2.1\nmail  300 A 192.0.2.2\n; no MX\n@\tIN\tTXT\t\"{ns} {serial}\"\n");
+
+        let err = dns_zonefile.generate(&zone, Some("{a}\n{serail}")).unwrap_err();
+        assert_eq!(err, "unknown placeholder `{serail}` on line 2");
+        assert!(dns_zonefile.generate(&zone, Some("{#each a}{ip|shout}{/each}")).is_err());
+        assert!(dns_zonefile.generate(&zone, Some("{#each a}{ip}")).is_err());
+        assert!(dns_zonefile.generate(&zone, Some("{ip}")).is_err());
+    }
+
+    #[test]
+    fn test_generate_options() {
+        let dns_zonefile = DnsZonefile::default();
+        let zone = dns_zonefile
+            .parse("$ORIGIN example.com.\n@ IN MX 10 mail\n@ IN MX 20 backup-mail\nwww.example.com. 300 IN A 192.0.2.1\n")
+            .unwrap();
+        let options = GenerateOptions {
+            align: true,
+            whitespace: Whitespace::Spaces,
+            type_case: TypeCase::Lower,
+            repeat_owner: false,
+            explicit_class: false,
+            ..Default::default()
+        };
+        let generated = dns_zonefile.generate_with(&zone, Some("{mx}{a}"), &options).unwrap();
+        assert_eq!(generated, "@ mx 10 mail\n  mx 20 backup-mail\nwww.example.com. 300 a 192.0.2.1\n");
+
+        // the SOA follows the same layout
+        let soa = dns_zonefile.parse("$ORIGIN example.com.\n@ 3600 IN SOA ns1 hostmaster 1 7200 3600 1209600 300\n").unwrap();
+        let generated = dns_zonefile.generate_with(&soa, Some("{soa}"), &options).unwrap();
+        assert!(generated.starts_with("@ 3600 soa ns1 hostmaster (\n1 ;serial\n7200 ;refresh\n"), "{generated}");
+        assert!(dns_zonefile.generate_with(&soa, None, &options).unwrap().contains("\n@ 3600 soa ns1 hostmaster (\n"));
+
+        let options = GenerateOptions { align: true, ..Default::default() };
+        let generated = dns_zonefile.generate_with(&zone, Some("{mx}"), &options).unwrap();
+        assert_eq!(generated, "@\tIN\tMX\t10\tmail\n@\tIN\tMX\t20\tbackup-mail\n");
+
+        let json = get_forward_zone_json();
+        let options = GenerateOptions { align: true, repeat_owner: false, explicit_class: false, ..Default::default() };
+        let generated = dns_zonefile.generate_with(&json, None, &options).unwrap();
+        assert!(generated.contains("_foobar._tcp\t200\tSRV\t0\t1\t9\told-slow-box.example.com.\n\t\t\tSRV\t0\t3\t9\tnew-fast-box.example.com."));
+        assert_eq!(dns_zonefile.parse(&generated).unwrap(), dns_zonefile.parse(&dns_zonefile.generate(&json, None).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_generate_pinned_clock() {
+        let dns_zonefile = DnsZonefile::default();
+        let mut json = get_forward_zone_json();
+        json.soa.as_mut().unwrap().serial = Serial::String("{time}".to_string());
+        let now = chrono::DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().to_utc();
+        let options = GenerateOptions { now: Some(now), ..Default::default() };
+        let generated = dns_zonefile.generate_with(&json, None, &options).unwrap();
+        assert!(generated.contains("; Exported  (yyyy-mm-ddThh:mm:ss.sssZ): 2024-05-01T12:00:00+00:00\n"));
+        assert!(generated.contains("1714564800\t ;serial"));
+        assert_eq!(generated, dns_zonefile.generate_with(&json, None, &options).unwrap());
+
+        let options = GenerateOptions { now: Some(now), exported_header: false, ..Default::default() };
+        let generated = dns_zonefile.generate_with(&json, None, &options).unwrap();
+        assert!(generated.starts_with("; Zone: MYDOMAIN.COM.\n\n$ORIGIN MYDOMAIN.COM.\n"));
+        assert!(!generated.contains("Exported"));
+    }
+
+    #[test]
+    fn test_generate_name_style() {
+        let dns_zonefile = DnsZonefile::default();
+        let zone = dns_zonefile
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "@ IN NS ns1.example.com.\n",
+                "@ IN NS ns.example.net.\n",
+                "www.example.com. IN A 192.0.2.1\n",
+                "EXAMPLE.COM. IN MX 10 mail\n",
+                "ftp IN CNAME www.example.com.\n",
+                "_sip._tcp IN SRV 0 5 5060 .\n",
+            ))
+            .unwrap();
+        let template = "{ns}{a}{mx}{cname}{srv}";
+        let options = GenerateOptions { names: NameStyle::Relative, ..Default::default() };
+        assert_eq!(
+            dns_zonefile.generate_with(&zone, Some(template), &options).unwrap(),
+            concat!(
+                "@\tIN\tNS\tns1\n",
+                "@\tIN\tNS\tns.example.net.\n",
+                "www\tIN\tA\t192.0.2.1\n",
+                "@\tIN\tMX\t10\tmail\n",
+                "ftp\tIN\tCNAME\twww\n",
+                "_sip._tcp\tIN\tSRV\t0\t5\t5060\t.\n",
+            )
+        );
+        let options = GenerateOptions { names: NameStyle::Absolute, ..Default::default() };
+        assert_eq!(
+            dns_zonefile.generate_with(&zone, Some(template), &options).unwrap(),
+            concat!(
+                "example.com.\tIN\tNS\tns1.example.com.\n",
+                "example.com.\tIN\tNS\tns.example.net.\n",
+                "www.example.com.\tIN\tA\t192.0.2.1\n",
+                "EXAMPLE.COM.\tIN\tMX\t10\tmail.example.com.\n",
+                "ftp.example.com.\tIN\tCNAME\twww.example.com.\n",
+                "_sip._tcp.example.com.\tIN\tSRV\t0\t5\t5060\t.\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let dns_zonefile = DnsZonefile::default();
+        let mut zone = dns_zonefile
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "WWW IN A 192.0.2.10\n",
+                "www.example.com. IN A 192.0.2.9\n",
+                "mail IN A 192.0.2.1\n",
+                "@ IN A 192.0.2.1\n",
+                "www IN A 192.0.2.10\n",
+                "@ IN MX 20 MAIL.example.com.\n",
+                "@ IN MX 10 mail.example.com.\n",
+                "WWW IN NSEC Mail.example.com. A NSEC\n",
+            ))
+            .unwrap();
+        zone.canonicalize().unwrap();
+        let nsec = &zone.nsec.as_ref().unwrap()[0];
+        assert_eq!((nsec.name.as_str(), nsec.next_domain.as_str()), ("www", "Mail.example.com."));
+        let a: Vec<(&str, &str)> = zone.a.as_ref().unwrap().iter().map(|r| (r.name.as_str(), r.ip.as_str())).collect();
+        assert_eq!(a, [("@", "192.0.2.1"), ("mail", "192.0.2.1"), ("www.example.com.", "192.0.2.9"), ("www", "192.0.2.10")]);
+        let mx: Vec<(u16, &str)> = zone.mx.as_ref().unwrap().iter().map(|r| (r.preference, r.host.as_str())).collect();
+        assert_eq!(mx, [(10, "mail.example.com."), (20, "mail.example.com.")]);
+
+        let mut shuffled = zone.clone();
+        shuffled.a.as_mut().unwrap().reverse();
+        let options = GenerateOptions { canonical: true, ..Default::default() };
+        assert_eq!(
+            dns_zonefile.generate_with(&zone, Some("{a}{mx}"), &options).unwrap(),
+            dns_zonefile.generate_with(&shuffled, Some("{a}{mx}"), &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_txt_character_strings() {
+        let key = "v=DKIM1; k=rsa; p=".to_string() + &"A".repeat(382);
+        let txt = Txt { name: "sel._domainkey".to_string(), txt: key.clone(), ttl: None };
+        let strings = txt.to_character_strings().unwrap();
+        assert_eq!(strings.len(), 2);
+        assert_eq!(strings[0].len(), 257);
+        assert_eq!(format!("{}{}", &strings[0][1..256], &strings[1][1..strings[1].len() - 1]), key);
+
+        let txt = Txt { txt: r#"say "hi" \ bye"#.to_string(), ..Default::default() };
+        assert_eq!(txt.to_character_strings().unwrap(), [r#""say \"hi\" \\ bye""#]);
+        let txt = Txt { txt: r#""v=DKIM1\; k=rsa" bare"#.to_string(), ..Default::default() };
+        assert_eq!(txt.to_character_strings().unwrap(), [r#""v=DKIM1\; k=rsa""#, "bare"]);
+
+        let dns_zonefile = DnsZonefile::default();
+        let mut zone = dns_zonefile.parse("$ORIGIN example.com.\n@ IN TXT \"x\"\n").unwrap();
+        zone.txt = Some(vec![Txt { name: "sel._domainkey".to_string(), txt: key, ttl: None }]);
+        let generated = dns_zonefile.generate(&zone, None).unwrap();
+        assert!(generated.contains(&format!("p={}\" \"{}\"\n", "A".repeat(237), "A".repeat(145))));
+        let options = GenerateOptions { multiline_txt: true, ..Default::default() };
+        let generated = dns_zonefile.generate_with(&zone, None, &options).unwrap();
+        assert!(generated.contains("\tTXT\t( \"v=DKIM1; k=rsa; p=AAA"));
+        let reparsed = dns_zonefile.parse(&generated).unwrap();
+        let strings = reparsed.txt.unwrap()[0].to_character_strings().unwrap();
+        assert_eq!(strings, zone.txt.unwrap()[0].to_character_strings().unwrap());
+    }
+
+    #[test]
+    fn test_write_to() {
+        let dns_zonefile = DnsZonefile::default();
+        let json = get_forward_zone_json();
+        let now = chrono::DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().to_utc();
+        let options = GenerateOptions { now: Some(now), align: true, ..Default::default() };
+        let mut out = Vec::new();
+        dns_zonefile.write_to(&json, &mut out, &options).unwrap();
+        let written = String::from_utf8(out).unwrap();
+        assert_eq!(written, dns_zonefile.generate_with(&json, None, &options).unwrap());
+        assert!(!written.contains("\n\n\n"));
+
+        let mut out = Vec::new();
+        let err = dns_zonefile.write_template_to(&json, "{a}{nope}", &mut out, &options).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_generate_dialects() {
+        let dns_zonefile = DnsZonefile::default();
+        let zone = dns_zonefile
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "@ 3600 IN SOA ns1 hostmaster 1 7200 3600 1209600 300\n",
+                "www IN A 192.0.2.1\n",
+                "mail 600 IN A 192.0.2.2\n",
+                "www IN NSEC mail.example.com. A NSEC\n",
+            ))
+            .unwrap();
+
+        let options = GenerateOptions { dialect: Dialect::Knot, ..Default::default() };
+        let generated = dns_zonefile.generate_with(&zone, Some("{a}"), &options).unwrap();
+        assert_eq!(generated, "www\t3600\tIN\tA\t192.0.2.1\nmail\t600\tIN\tA\t192.0.2.2\n");
+
+        let options = GenerateOptions { dialect: Dialect::Nsd, ..Default::default() };
+        let generated = dns_zonefile.generate_with(&zone, Some("{$ttl}\n{a}"), &options).unwrap();
+        assert!(generated.starts_with("$TTL 3600\nwww\tIN\tA"));
+
+        let options = GenerateOptions { dialect: Dialect::PowerDns, ..Default::default() };
+        let err = dns_zonefile.generate_with(&zone, None, &options).unwrap_err();
+        assert_eq!(err, "NSEC records are not supported by PowerDns: `www NSEC`");
+        let (generated, warnings) = dns_zonefile.generate_lenient(&zone, None, &options).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(!generated.contains("NSEC\t"));
+        assert!(dns_zonefile.generate_with(&zone, None, &GenerateOptions::default()).unwrap().contains("IN\tNSEC\t"));
+
+        let mut zone = zone;
+        zone.soa.as_mut().unwrap().serial = Serial::String("next".to_string());
+        let options = GenerateOptions { dialect: Dialect::Knot, ..Default::default() };
+        assert!(dns_zonefile.generate_with(&zone, None, &options).is_err());
+    }
+
+    #[test]
+    fn test_serial_strategies() {
+        use std::cmp::Ordering;
+        let now = chrono::DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().to_utc();
+        assert_eq!(Serial::Number(u32::MAX).compare(&Serial::Number(1)), Some(Ordering::Less));
+        assert_eq!(Serial::Number(1).compare(&Serial::Number(1 << 31)), Some(Ordering::Less));
+        assert_eq!(Serial::Number(0).compare(&Serial::Number(1 << 31)), None);
+        assert_eq!(Serial::String("{time}".to_string()).compare(&Serial::Number(1)), None);
+
+        assert_eq!(SerialStrategy::Increment.next(u32::MAX, now), 0);
+        assert_eq!(SerialStrategy::UnixTime.next(1406291485, now), 1714564800);
+        assert_eq!(SerialStrategy::UnixTime.next(1714564800, now), 1714564801);
+        assert_eq!(SerialStrategy::DateCounter.next(2024043005, now), 2024050100);
+        assert_eq!(SerialStrategy::DateCounter.next(2024050100, now), 2024050101);
+
+        let mut soa = get_forward_zone_json().soa.unwrap();
+        assert_eq!(soa.bump_serial(SerialStrategy::Increment, now), Ok(1406291486));
+        assert_eq!(soa.serial, Serial::Number(1406291486));
+        soa.serial = Serial::String("{time}".to_string());
+        assert!(soa.bump_serial(SerialStrategy::Increment, now).is_err());
+    }
+
+    #[test]
+    fn test_generate_bump_serial() {
+        let dns_zonefile = DnsZonefile::default();
+        let previous = get_forward_zone_json();
+        let now = chrono::DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().to_utc();
+        let bump = SerialBump { strategy: SerialStrategy::DateCounter, previous: previous.clone() };
+        let options = GenerateOptions { now: Some(now), bump_serial: Some(bump), ..Default::default() };
+
+        let mut zone = previous.clone();
+        zone.soa.as_mut().unwrap().serial = Serial::Number(1);
+        let generated = dns_zonefile.generate_with(&zone, None, &options).unwrap();
+        assert!(generated.contains("1406291485\t ;serial"));
+
+        zone.a.as_mut().unwrap()[0].ip = "192.0.2.1".to_string();
+        let generated = dns_zonefile.generate_with(&zone, None, &options).unwrap();
+        assert!(generated.contains("2024050100\t ;serial"));
+
+        zone.soa.as_mut().unwrap().serial = Serial::Number(2030010100);
+        let generated = dns_zonefile.generate_with(&zone, None, &options).unwrap();
+        assert!(generated.contains("2030010100\t ;serial"));
+
+        // a serial bumped by hand is kept even when nothing else changed
+        let mut zone = previous.clone();
+        zone.soa.as_mut().unwrap().serial = Serial::Number(1406291490);
+        let generated = dns_zonefile.generate_with(&zone, None, &options).unwrap();
+        assert!(generated.contains("1406291490\t ;serial"));
+
+        // absolute names and explicit ttls are the same content
+        let previous = dns_zonefile
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "$TTL 3600\n",
+                "@ IN SOA ns1 hostmaster 5 7200 3600 1209600 300\n",
+                "@ IN NS ns1\n",
+                "www IN A 192.0.2.1\n",
+            ))
+            .unwrap();
+        let zone = dns_zonefile
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "example.com. 3600 IN SOA ns1.example.com. hostmaster.example.com. 1 7200 3600 1209600 300\n",
+                "example.com. 3600 IN NS ns1.example.com.\n",
+                "WWW.example.com. 3600 IN A 192.0.2.1\n",
+            ))
+            .unwrap();
+        let bump = SerialBump { strategy: SerialStrategy::Increment, previous };
+        let options = GenerateOptions { now: Some(now), bump_serial: Some(bump), ..Default::default() };
+        let generated = dns_zonefile.generate_with(&zone, None, &options).unwrap();
+        assert!(generated.contains("5\t ;serial"));
+    }
+
+    #[test]
+    fn test_reverse_zones() {
+        let dns_zonefile = DnsZonefile::default();
+        let forward = dns_zonefile
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "$TTL 3600\n",
+                "@ IN SOA ns1 hostmaster 1 7200 3600 1209600 300\n",
+                "@ IN NS ns1\n",
+                "sub IN NS ns.sub\n",
+                "ns1 IN A 192.0.2.1\n",
+                "www 600 IN A 192.0.2.70\n",
+                "www IN AAAA 2001:db8::1\n",
+                "web IN CNAME www\n",
+            ))
+            .unwrap();
+        let networks: Vec<ReverseNetwork> =
+            ["192.0.2.0/24", "192.0.2.64/26", "2001:db8::/32"].iter().map(|n| n.parse().unwrap()).collect();
+        let zones = DnsRecord::reverse_zones(std::slice::from_ref(&forward), &networks);
+
+        assert_eq!(zones[0].origin.as_deref(), Some("2.0.192.in-addr.arpa."));
+        let ptr = zones[0].ptr.as_ref().unwrap();
+        assert_eq!(ptr.len(), 1);
+        assert_eq!((ptr[0].name.as_str(), ptr[0].host.as_str()), ("1", "ns1.example.com."));
+        assert_eq!(zones[0].soa.as_ref().unwrap().mname, "ns1.example.com.");
+        assert_eq!(zones[0].ns.as_ref().unwrap().len(), 1);
+        assert_eq!(zones[0].ttl, Some(3600));
+
+        assert_eq!(zones[1].origin.as_deref(), Some("64/26.2.0.192.in-addr.arpa."));
+        let ptr = &zones[1].ptr.as_ref().unwrap()[0];
+        assert_eq!((ptr.name.as_str(), ptr.fullname.as_str(), ptr.ttl), ("70", "70.64/26.2.0.192.in-addr.arpa.", Some(600)));
+
+        assert_eq!(zones[2].origin.as_deref(), Some("8.b.d.0.1.0.0.2.ip6.arpa."));
+        assert_eq!(zones[2].ptr.as_ref().unwrap()[0].name, "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0");
+
+        let generated = dns_zonefile.generate(&zones[1], None).unwrap();
+        assert!(generated.contains("70\t600\tIN\tPTR\twww.example.com."));
+        assert!("192.0.2.1/24".parse::<ReverseNetwork>().is_err());
+        assert!("192.0.3.0/23".parse::<ReverseNetwork>().is_err());
+
+        // networks between octet boundaries are split into covering zones
+        let network: ReverseNetwork = "192.0.0.0/20".parse().unwrap();
+        let split = network.zones();
+        assert_eq!(split.len(), 16);
+        assert_eq!(split[15].origin(), "15.0.192.in-addr.arpa.");
+        let zones = DnsRecord::reverse_zones(&[forward], &[network]);
+        assert_eq!(zones.len(), 16);
+        assert_eq!(zones[2].origin.as_deref(), Some("2.0.192.in-addr.arpa."));
+        assert_eq!(zones[2].ptr.as_ref().map(Vec::len), Some(2));
+        assert!(zones[3].ptr.is_none());
+        assert!("2001:db8::/33".parse::<ReverseNetwork>().is_err());
+    }
+
+    #[test]
+    fn test_ptr_reverse_names() {
+        let ip: std::net::IpAddr = "2001:db8::1".parse().unwrap();
+        let ptr = Ptr::from_ip(ip, "www.example.com.", "8.b.d.0.1.0.0.2.ip6.arpa.");
+        assert_eq!(ptr.name, "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0");
+        assert_eq!(ptr.ip(), Some(ip));
+        let ptr = Ptr::from_ip("192.0.2.1".parse().unwrap(), "www.example.com.", "example.com.");
+        assert_eq!(ptr.name, "1.2.0.192.in-addr.arpa.");
+        assert_eq!(ptr.ip(), Some("192.0.2.1".parse().unwrap()));
+
+        let zone = DnsZonefile::default()
+            .parse(concat!(
+                "2.0.192.in-addr.arpa. 3600 IN SOA ns1.example.com. hostmaster.example.com. 1 7200 3600 1209600 300\n",
+                "1 IN PTR ns1.example.com.\n",
+                "3.2.0.192.IN-ADDR.ARPA. IN PTR www.example.com.\n",
+                "$ORIGIN 64/26.2.0.192.in-addr.arpa.\n",
+                "70 IN PTR mail.example.com.\n",
+            ))
+            .unwrap();
+        let ptr = zone.ptr.unwrap();
+        assert_eq!(ptr[0].fullname, "1.2.0.192.in-addr.arpa.");
+        assert_eq!(ptr[1].fullname, "3.2.0.192.IN-ADDR.ARPA.");
+        assert_eq!(ptr[1].ip(), Some("192.0.2.3".parse().unwrap()));
+        assert_eq!(ptr[2].ip(), Some("192.0.2.70".parse().unwrap()));
+        assert_eq!(Ptr { fullname: "2.0.192.in-addr.arpa.".to_string(), ..ptr[0].clone() }.ip(), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let reverse = fs::read_to_string("tests/zonefile_reverse_ipv4.txt").unwrap();
+        assert_eq!(DnsZonefile::default().parse(&reverse).unwrap().validate().findings, []);
+        let forward = get_forward_zone_json().validate();
+        assert!(forward.findings.iter().any(|f| f.kind == FindingKind::TargetIsCname && f.name == "MYDOMAIN.COM."));
+        let zone = DnsZonefile::default()
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "@ 3600 IN SOA ns1 hostmaster 1 7200 3600 1209600 300\n",
+                "@ IN MX 10 mail\n",
+                "ns1 IN A 192.0.2.1\n",
+                "ns1 600 IN A 192.0.2.2\n",
+                "www IN A 192.0.2.3\n",
+                "www IN A 192.0.2.3\n",
+                "www IN TXT \"hello\"\n",
+                "www IN CNAME web\n",
+                "mail IN CNAME www\n",
+                "other.example.net. IN A 192.0.2.4\n",
+            ))
+            .unwrap();
+        let report = zone.validate();
+        assert!(!report.is_valid());
+        let kinds: Vec<(FindingKind, &str)> = report.findings.iter().map(|f| (f.kind, f.name.as_str())).collect();
+        assert_eq!(
+            kinds,
+            [
+                (FindingKind::MissingApexNs, "example.com."),
+                (FindingKind::TargetIsCname, "example.com."),
+                (FindingKind::TtlMismatch, "ns1.example.com."),
+                (FindingKind::OutOfZone, "other.example.net."),
+                (FindingKind::DuplicateRecord, "www.example.com."),
+                (FindingKind::CnameAndOtherData, "www.example.com."),
+            ]
+        );
+        assert_eq!(report.findings[5].to_string(), "www.example.com. CNAME: CNAME and other data (A, TXT)");
+
+        let text = "$ORIGIN example.com.\n@ IN SOA a b 1 2 3 4 5\n@ IN SOA a b 2 2 3 4 5\n";
+        let (zone, warnings) = DnsZonefile::default().parse_lenient(text).unwrap();
+        assert_eq!(zone.soa.as_ref().unwrap().serial.to_string(), "2");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].to_string(), "more than one SOA record, replaces the one with serial 1: `@ IN SOA a b 2 2 3 4 5`");
+        assert!(DnsZonefile::default().parse_strict(text).is_err());
+    }
+
+    #[test]
+    fn test_validate_delegations() {
+        let zone = DnsZonefile::default()
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "@ 3600 IN SOA ns1 hostmaster 1 7200 3600 1209600 300\n",
+                "@ IN NS ns1\n",
+                "ns1 IN A 192.0.2.1\n",
+                "sub IN NS ns1.sub\n",
+                "sub IN NS ns2.sub\n",
+                "sub IN NS ns.example.net.\n",
+                "sub IN MX 10 mail\n",
+                "ns1.sub IN A 192.0.2.10\n",
+                "old.sub IN A 192.0.2.11\n",
+                "www.sub IN TXT \"hidden\"\n",
+                "deep.sub IN NS ns.deep.sub\n",
+            ))
+            .unwrap();
+        let delegations = zone.delegations();
+        assert_eq!(delegations.len(), 1);
+        assert_eq!(delegations[0].name, "sub.example.com.");
+        assert_eq!(delegations[0].in_bailiwick().count(), 2);
+
+        let report = zone.validate();
+        assert!(!report.is_valid());
+        let kinds: Vec<(FindingKind, &str)> = report
+            .findings
+            .iter()
+            .filter(|f| matches!(f.kind, FindingKind::MissingGlue | FindingKind::OccludedData | FindingKind::StrayGlue))
+            .map(|f| (f.kind, f.name.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (FindingKind::MissingGlue, "sub.example.com."),
+                (FindingKind::OccludedData, "deep.sub.example.com."),
+                (FindingKind::OccludedData, "sub.example.com."),
+                (FindingKind::StrayGlue, "old.sub.example.com."),
+                (FindingKind::OccludedData, "www.sub.example.com."),
+            ]
+        );
+        assert!(report.findings[0].message.contains("ns2.sub.example.com."));
+
+        // glue of a nameserver named like the child zone sits at the cut
+        let zone = DnsZonefile::default()
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "@ 3600 IN SOA ns1 hostmaster 1 7200 3600 1209600 300\n",
+                "@ IN NS ns1\n",
+                "ns1 IN A 192.0.2.1\n",
+                "child IN NS child.example.com.\n",
+                "child IN A 192.0.2.20\n",
+                "child IN TXT \"hidden\"\n",
+            ))
+            .unwrap();
+        let findings: Vec<(FindingKind, RecordType)> = zone.validate().findings.iter().map(|f| (f.kind, f.rtype)).collect();
+        assert_eq!(findings, [(FindingKind::OccludedData, RecordType::Txt)]);
+    }
+
+    #[test]
+    fn test_lint() {
+        let config: LintConfig = serde_json::from_str(
+            r#"{"rules": {"min-ttl": {"severity": "error", "min": 600}, "hostname-syntax": {"enabled": false}}}"#,
+        )
+        .unwrap();
+        let linter = Linter::new(config);
+        let text = concat!(
+            "$ORIGIN example.com.\n",
+            "$TTL 3600\n",
+            "@ IN SOA ns1 hostmaster 1 7200 3600 1209600 300\n",
+            "@ IN NS ns1\n",
+            "ns1 300 IN A 192.0.2.1\n",
+            "; lint:ignore min-ttl\n",
+            "www 60 IN A 192.0.2.2\n",
+            "* IN MX 10 mail ; lint:ignore no-wildcard-mx\n",
+            "*.sub IN MX 10 mail\n",
+            "bad_name IN A 192.0.2.3\n",
+        );
+        let findings = DnsZonefile::default().lint(text, &linter).unwrap();
+        let rules: Vec<(&str, &str, Severity)> = findings.iter().map(|f| (f.rule.as_str(), f.name.as_str(), f.severity)).collect();
+        assert_eq!(
+            rules,
+            [
+                ("min-ttl", "ns1.example.com.", Severity::Error),
+                ("require-caa", "example.com.", Severity::Warning),
+                ("no-wildcard-mx", "*.sub.example.com.", Severity::Warning),
+            ]
+        );
+        assert_eq!(findings[0].to_string(), "ns1.example.com. A: TTL 300 is below 600 [min-ttl]");
+
+        let findings = DnsZonefile::default().lint(&format!("; lint:ignore-file all\n{text}"), &linter).unwrap();
+        assert!(findings.is_empty());
+        let zone = DnsZonefile::default().parse(text).unwrap();
+        let findings = Linter::new(LintConfig::default()).lint(&zone).unwrap();
+        assert!(findings.iter().any(|f| f.rule == "hostname-syntax" && f.message.contains("`bad_name`")));
+        let config = LintConfig { rules: [("no-such-rule".to_string(), RuleConfig::default())].into() };
+        assert_eq!(Linter::new(config).lint(&zone).unwrap_err(), "unknown lint rule `no-such-rule`");
+    }
+
+    #[test]
+    fn test_spf() {
+        let record: SpfRecord = "v=spf1 -ip4:192.0.2.0/24 ip6:2001:db8::/32 a:mail.example.com/28//64 mx ~include:_spf.example.net redirect=_spf.example.com".parse().unwrap();
+        assert_eq!(record.terms.len(), 6);
+        assert_eq!(record.lookup_terms(), 4);
+        assert_eq!(record.redirect(), Some("_spf.example.com"));
+        assert!(matches!(
+            &record.terms[2],
+            Term::Directive(Directive { mechanism: Mechanism::A { cidr4: Some(28), cidr6: Some(64), .. }, .. })
+        ));
+        assert_eq!(
+            record.to_string(),
+            "v=spf1 -ip4:192.0.2.0/24 ip6:2001:db8::/32 a:mail.example.com/28//64 mx ~include:_spf.example.net redirect=_spf.example.com"
+        );
+        assert_eq!("v=spf1 ip4:192.0.2.0/33".parse::<SpfRecord>().unwrap_err(), "invalid prefix length in `ip4:192.0.2.0/33`");
+        assert_eq!("v=spf1 foo:bar".parse::<SpfRecord>().unwrap_err(), "unknown mechanism `foo:bar`");
+        assert!("v=spf1 exists:%{i}._spf.%{d}".parse::<SpfRecord>().is_ok());
+        assert!("v=spf1 exists:%{q}.example.com".parse::<SpfRecord>().is_err());
+        assert!("v=spf10 -all".parse::<SpfRecord>().is_err());
+
+        let dns_zonefile = DnsZonefile::default();
+        let zone = dns_zonefile
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "@ IN TXT \"v=spf1 mx include:_spf.example.com \" \"include:_spf.example.net -all\"\n",
+                "@ IN MX 10 mail\n",
+                "_spf IN TXT \"v=spf1 a a:b.example.com a:c.example.com a:d.example.com a:e.example.com a:f.example.com a:g.example.com ~all\"\n",
+                "www IN TXT \"v=spf1 -all\"\n",
+                "www IN TXT \"v=spf1 +all\"\n",
+                "old IN SPF \"v=spf1 ptr -all\"\n",
+            ))
+            .unwrap();
+        let other = dns_zonefile
+            .parse("$ORIGIN example.net.\n_spf IN TXT \"v=spf1 a:x.example.net a:y.example.net ?all\"\n")
+            .unwrap();
+        assert_eq!(zone.spf_records().len(), 5);
+
+        let reports = zone.check_spf(std::slice::from_ref(&other));
+        let apex = reports.iter().find(|r| r.name == "example.com.").unwrap();
+        assert_eq!(apex.lookups, 12);
+        assert_eq!(apex.errors, ["12 DNS lookups, more than the limit of 10"]);
+        let without_other = zone.check_spf(&[]);
+        let apex = without_other.iter().find(|r| r.name == "example.com.").unwrap();
+        assert_eq!(apex.lookups, 10);
+        assert!(apex.is_valid());
+        assert_eq!(apex.warnings, ["no policy for _spf.example.net. in the zones, its lookups are not counted"]);
+
+        let www = reports.iter().find(|r| r.name == "www.example.com.").unwrap();
+        assert!(www.warnings[0].starts_with("2 SPF policies at one owner"));
+        let old = reports.iter().find(|r| r.name == "old.example.com.").unwrap();
+        assert_eq!(old.warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_mail_records() {
+        const RSA_2048: &str = "MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAt4bLdpAAglMcw3vIDbK4pTc5YpgSUYvjq+1IMC7F+pjRXYrTDvTVHOYHP3VZqfyEilIw5Q6bRIwwiYN9UZjlLfrsXMScDKAF0xM0mHsK7zyy3e4NOS1tvNEicIxVBh+9mRHoki2XU9YyxyzzFcu4/qhjPf0UM7RTSEyVy9qmJ77LUxIdS8yV0vL3dG5NElSiLxZygYBU9DZfpWJpooF7wpVOU7vcLq6tSX+VKSsp3b5TplxHav8GA1tcKtnZ0ldG6rDIgwrs69kbrK3iCNaC3u5RDPZjBeqpc+tOHOyrnlE90P5XgPIEafhktH++4nOvFyVF0ZOkfJKfg19QuEZuhQIDAQAB";
+        const RSA_512: &str = "MFwwDQYJKoZIhvcNAQEBBQADSwAwSAJBAKgzXWlBEwS9dJ0zHtxgQdA7NN+JdTvvFDUtEyax5wWsvHbgwMYe/CeqOq0GqTTskQYEI4AfExAOXGfZTzjkSUMCAwEAAQ==";
+
+        let dmarc: Dmarc = "v=DMARC1; p=reject; rua=mailto:dmarc@example.com; pct=50; adkim=s".parse().unwrap();
+        assert_eq!(dmarc.policy, DmarcPolicy::Reject);
+        assert_eq!(dmarc.percent, Some(50));
+        assert_eq!(dmarc.to_string(), "v=DMARC1; p=reject; pct=50; rua=mailto:dmarc@example.com; adkim=s");
+        assert_eq!("v=DMARC1; pct=50".parse::<Dmarc>().unwrap_err(), "DMARC record needs a `p` tag");
+        assert!("p=reject; v=DMARC1".parse::<Dmarc>().is_err());
+        assert!("v=DMARC1; p=reject; pct=150".parse::<Dmarc>().is_err());
+        assert!("v=DMARC1; p=reject; rua=dmarc@example.com".parse::<Dmarc>().is_err());
+
+        let dkim: Dkim = format!("v=DKIM1; k=rsa; p={RSA_2048}").parse().unwrap();
+        assert_eq!(dkim.key_bits(), Some(2048));
+        assert_eq!(
+            format!("v=DKIM1; p={RSA_512}").parse::<Dkim>().unwrap_err(),
+            "RSA key of 512 bits, at least 1024 are required"
+        );
+        assert!("v=DKIM1; k=ed25519; p=gAJ+mvQYz4NY0CGiVr0J7Mdf2cDanlNYQHdO5Je+eXw=".parse::<Dkim>().is_ok());
+        assert!("v=DKIM1; p=".parse::<Dkim>().unwrap().is_revoked());
+
+        assert_eq!("v=STSv1; id=20240501T1200".parse::<MtaSts>().unwrap().id, "20240501T1200");
+        assert!("v=STSv1; id=2024-05-01".parse::<MtaSts>().is_err());
+        assert!("v=TLSRPTv1; rua=mailto:tls@example.com,https://report.example.com/tls".parse::<TlsRpt>().is_ok());
+        assert!("v=TLSRPTv1; rua=ftp://example.com".parse::<TlsRpt>().is_err());
+
+        let dns_zonefile = DnsZonefile::default();
+        let mut zone = dns_zonefile
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "_dmarc IN TXT \"v=DMARC1; p=none\"\n",
+                "_mta-sts IN TXT \"v=STSv1; id=1\"\n",
+                "_smtp._tls IN TXT \"v=TLSRPTv1; rua=mailto:tls@example.com\"\n",
+                "old._domainkey IN TXT \"v=DKIM1; p=MFwwDQYJKoZIhvcNAQEBBQADSwAwSAJBAKgzXWlBEwS9dJ0zHtxgQdA7NN+JdTvvFDUtEyax5wWsvHbgwMYe/CeqOq0GqTTskQYEI4AfExAOXGfZTzjkSUMCAwEAAQ==\"\n",
+                "www IN TXT \"hello\"\n",
+            ))
+            .unwrap();
+        zone.txt.as_mut().unwrap().push(Dkim::new(DkimKeyType::Rsa, RSA_2048).to_txt("mail._domainkey").unwrap());
+        let entries = zone.mail_records();
+        assert_eq!(entries.len(), 5);
+        assert!(matches!(entries[0].policy, Ok(MailPolicy::Dmarc(_))));
+        assert!(matches!(entries[1].policy, Ok(MailPolicy::MtaSts(_))));
+        assert!(matches!(entries[2].policy, Ok(MailPolicy::TlsRpt(_))));
+        assert_eq!(entries[3].name, "old._domainkey.example.com.");
+        assert!(entries[3].policy.is_err());
+        assert_eq!(entries[4].policy, Ok(MailPolicy::Dkim(Dkim::new(DkimKeyType::Rsa, RSA_2048))));
+
+        let generated = dns_zonefile.generate(&zone, Some("{txt}")).unwrap();
+        let reparsed = dns_zonefile.parse(&format!("$ORIGIN example.com.\n{generated}")).unwrap();
+        assert_eq!(reparsed.mail_records(), entries);
+    }
+
+    #[test]
+    fn test_caa() {
+        let zone = get_parsed_forward_zone();
+        let caa = zone.caa.as_ref().unwrap();
+        let Ok(CaaProperty::Issue(issuer)) = caa[0].property() else { panic!("issue property") };
+        assert_eq!(issuer.issuer.as_deref(), Some("ca.example.net"));
+        assert_eq!(issuer.account(), Some("230123"));
+        assert_eq!(caa[1].property(), Ok(CaaProperty::Iodef("mailto:security@example.com".to_string())));
+        assert!(!caa[0].is_critical());
+
+        let issuer: CaaIssuer = "ca.example.net; account=1; validationmethods=dns-01,http-01".parse().unwrap();
+        assert_eq!(issuer.validation_methods(), ["dns-01", "http-01"]);
+        assert_eq!(issuer.to_string(), "ca.example.net; account=1; validationmethods=dns-01,http-01");
+        assert_eq!(";".parse::<CaaIssuer>().unwrap().issuer, None);
+        assert!("ca_example.net".parse::<CaaIssuer>().is_err());
+        assert!(CaaProperty::parse(&CaaTag::Iodef, "ftp://example.com").is_err());
+        assert!(CaaProperty::parse(&CaaTag::Iodef, "https://").is_err());
+        assert_eq!("TBS".parse::<CaaTag>(), Ok(CaaTag::Unknown("TBS".to_string())));
+
+        let mut zone = DnsZonefile::default()
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "@ IN CAA 0 issue \"letsencrypt.org\"\n",
+                "@ IN CAA 0 issuewild \";\"\n",
+                "shop IN CAA 0 issue \"ca.example.net; account=1\"\n",
+            ))
+            .unwrap();
+        assert!(zone.caa_permits("www.example.com.", "letsencrypt.org", false));
+        assert!(!zone.caa_permits("www", "ca.example.net", false));
+        assert!(!zone.caa_permits("www", "letsencrypt.org", true));
+        assert!(zone.caa_permits("a.shop", "ca.example.net", false));
+        assert!(!zone.caa_permits("a.shop", "letsencrypt.org", false));
+        assert!(!zone.caa_permits("a.shop", "letsencrypt.org", true));
+        assert_eq!(zone.relevant_caa("a.b.shop.example.com.").len(), 1);
+
+        let property = CaaProperty::Unknown { tag: "tbs".to_string(), value: "x".to_string() };
+        zone.caa.as_mut().unwrap().push(Caa::new("shop", &property, true));
+        assert!(!zone.caa_permits("a.shop", "ca.example.net", false));
+        assert!(zone.caa_permits("www", "letsencrypt.org", false));
+        assert_eq!(Caa::new("@", &CaaProperty::Issue(CaaIssuer::new("ca.example.net")), false).value, "ca.example.net");
+        // values are stored without quotes, JSON of older versions still has them
+        let parsed = get_parsed_forward_zone().caa.unwrap();
+        assert_eq!(parsed[0].value, "ca.example.net; account=230123");
+        assert_eq!(get_forward_zone_json().caa.unwrap(), parsed);
+        let generated = DnsZonefile::default().generate(&zone, Some("{caa}")).unwrap();
+        assert!(generated.contains("CAA\t128\ttbs\t\"x\""));
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = DnsZonefile::default()
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "$TTL 3600\n",
+                "@ IN SOA ns1 admin 1 7200 3600 1209600 3600\n",
+                "@ IN NS ns1\n",
+                "@ IN MX 10 mail\n",
+                "ns1 IN A 192.0.2.1\n",
+                "www IN A 192.0.2.10\n",
+                "mail IN A 192.0.2.20\n",
+            ))
+            .unwrap();
+        let new = DnsZonefile::default()
+            .parse(concat!(
+                "$TTL 3600\n",
+                "example.com. IN SOA ns1.example.com. admin.example.com. 2 7200 3600 1209600 3600\n",
+                "example.com. IN NS NS1.EXAMPLE.COM.\n",
+                "NS1.example.com. IN A 192.0.2.1\n",
+                "www.example.com. IN A 192.0.2.11\n",
+                "mail.example.com. 300 IN A 192.0.2.20\n",
+                "example.com. IN TXT \"v=spf1 -all\"\n",
+            ))
+            .unwrap();
+        assert!(old.diff(&old).is_empty());
+        let diff = old.diff(&new);
+        let summary: Vec<(&str, RecordType, usize)> =
+            diff.rrsets.iter().map(|r| (r.name.as_str(), r.rtype, r.changes.len())).collect();
+        assert_eq!(
+            summary,
+            [
+                ("example.com.", RecordType::Soa, 1),
+                ("example.com.", RecordType::Mx, 1),
+                ("example.com.", RecordType::Txt, 1),
+                ("mail.example.com.", RecordType::A, 1),
+                ("www.example.com.", RecordType::A, 1),
+            ]
+        );
+        let changes: Vec<&RecordChange> = diff.changes().collect();
+        assert!(matches!(changes[1], RecordChange::Removed { .. }));
+        assert!(matches!(changes[2], RecordChange::Added { .. }));
+        assert!(matches!(changes[3], RecordChange::Ttl { .. }));
+        assert!(matches!(changes[4], RecordChange::Modified { .. }));
+        assert!(diff.to_string().contains(concat!(
+            "@@ mail.example.com. A @@\n",
+            "-mail.example.com.\t3600\tIN\tA\t192.0.2.20\n",
+            "+mail.example.com.\t300\tIN\tA\t192.0.2.20\t; ttl only\n",
+            "@@ www.example.com. A @@\n",
+            "-www.example.com.\t3600\tIN\tA\t192.0.2.10\n",
+            "+www.example.com.\t3600\tIN\tA\t192.0.2.11\n",
+        )));
+        assert!(diff.to_string().contains("-example.com.\t3600\tIN\tMX\t10\tmail.example.com.\n"));
+        let json = serde_json::to_string(&diff).unwrap();
+        assert!(json.contains(r#"{"change":"ttl","old":{"a":"#));
+        assert_eq!(serde_json::from_str::<ZoneDiff>(&json).unwrap(), diff);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut zone = DnsZonefile::default()
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "$TTL 3600\n",
+                "@ IN SOA ns1 admin 1 7200 3600 1209600 3600\n",
+                "@ IN NS ns1\n",
+                "ns1 IN A 192.0.2.1\n",
+                "www IN A 192.0.2.10\n",
+                "www IN A 192.0.2.11\n",
+                "www IN TXT \"web\"\n",
+            ))
+            .unwrap();
+        let new = DnsZonefile::default()
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "$TTL 3600\n",
+                "@ IN SOA ns1 admin 2 7200 3600 1209600 3600\n",
+                "@ IN NS ns1\n",
+                "ns1 300 IN A 192.0.2.1\n",
+                "www IN A 192.0.2.12\n",
+                "mail IN A 192.0.2.20\n",
+            ))
+            .unwrap();
+        let patch = ZonePatch::from(&zone.diff(&new));
+        let mut patched = zone.clone();
+        patched.apply(&patch).unwrap();
+        assert!(patched.diff(&new).is_empty());
+        // the same patch again finds the old records gone and changes nothing
+        let before = patched.clone();
+        assert!(patched.apply(&patch).is_err());
+        assert_eq!(patched, before);
+
+        let a = |name: &str, ip: &str| Record::A(A { name: name.to_string(), ip: ip.to_string(), ttl: None });
+        let patch = ZonePatch {
+            preconditions: vec![Precondition::Exists { record: a("WWW.example.com.", "192.0.2.10") }],
+            operations: vec![
+                PatchOperation::ReplaceRrset {
+                    name: "www".to_string(),
+                    rtype: RecordType::A,
+                    records: vec![a("www", "192.0.2.13")],
+                },
+                PatchOperation::DeleteName { name: "@".to_string() },
+                PatchOperation::Add { record: a("mail", "192.0.2.20") },
+            ],
+        };
+        zone.apply(&patch).unwrap();
+        assert_eq!(zone.a.as_ref().unwrap().iter().map(|a| a.ip.as_str()).collect::<Vec<_>>(), ["192.0.2.1", "192.0.2.13", "192.0.2.20"]);
+        assert!(zone.ns.is_none() && zone.soa.is_some());
+        assert!(zone.apply(&patch).unwrap_err().starts_with("precondition failed"));
+        let delete = ZonePatch { operations: vec![PatchOperation::Delete { record: a("www", "192.0.2.10") }], ..Default::default() };
+        assert!(zone.apply(&delete).is_err());
+        let rrset = PatchOperation::DeleteRrset { name: "www.example.com.".to_string(), rtype: RecordType::Txt };
+        zone.apply(&ZonePatch { operations: vec![rrset], ..Default::default() }).unwrap();
+        assert!(zone.txt.is_none());
+
+        let json = serde_json::to_string(&patch).unwrap();
+        assert!(json.contains(r#"{"op":"replace-rrset","name":"www","rtype":"A","records":[{"a":"#));
+        assert_eq!(serde_json::from_str::<ZonePatch>(&json).unwrap(), patch);
+    }
+
+    #[test]
+    fn test_update() {
+        let old = DnsZonefile::default()
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "$TTL 3600\n",
+                "@ IN SOA ns1 admin 1 7200 3600 1209600 3600\n",
+                "@ IN NS ns1\n",
+                "ns1 IN A 192.0.2.1\n",
+                "www IN A 192.0.2.10\n",
+            ))
+            .unwrap();
+        let new = DnsZonefile::default()
+            .parse(concat!(
+                "$ORIGIN example.com.\n",
+                "$TTL 3600\n",
+                "@ IN SOA ns1 admin 2 7200 3600 1209600 3600\n",
+                "@ IN NS ns1\n",
+                "ns1 IN A 192.0.2.1\n",
+                "www IN A 192.0.2.11\n",
+                "mail IN A 192.0.2.20\n",
+            ))
+            .unwrap();
+        let update = old.update_to(&new);
+        let key = TsigKey::from_base64("update-key", "c2VjcmV0").unwrap();
+        assert_eq!(
+            update.to_nsupdate(Some("127.0.0.1"), Some(&key)),
+            concat!(
+                "server 127.0.0.1\n",
+                "key hmac-sha256:update-key c2VjcmV0\n",
+                "zone example.com.\n",
+                "prereq yxrrset example.com. SOA ns1.example.com. admin.example.com. 1 7200 3600 1209600 3600\n",
+                "prereq nxrrset mail.example.com. A\n",
+                "prereq yxrrset www.example.com. A 192.0.2.10\n",
+                "update add example.com. 3600 SOA ns1.example.com. admin.example.com. 2 7200 3600 1209600 3600\n",
+                "update add mail.example.com. 3600 A 192.0.2.20\n",
+                "update delete www.example.com. A 192.0.2.10\n",
+                "update add www.example.com. 3600 A 192.0.2.11\n",
+                "send\n",
+            )
+        );
+
+        let wire = update.to_wire(0x1234).unwrap();
+        // id, opcode UPDATE, one zone, three prerequisites, four updates, no additional records
+        assert_eq!(wire[..12], [0x12, 0x34, 0x28, 0, 0, 1, 0, 3, 0, 4, 0, 0]);
+        let now = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let signed = update.to_signed_wire(0x1234, &key, now).unwrap();
+        assert_eq!(signed[..10], wire[..10]);
+        assert_eq!(signed[10..12], [0, 1]);
+        let tsig = &signed[wire.len()..];
+        assert!(tsig.starts_with(b"\x0aupdate-key\x00\x00\xfa\x00\xff\x00\x00\x00\x00"));
+        assert!(tsig[22..].starts_with(b"\x0bhmac-sha256\x00\x00\x00\x65\x53\xf1\x00\x01\x2c\x00\x20"));
+        // the MAC covers the unsigned message and the TSIG variables (RFC 8945 section 4.3.3)
+        use hmac::Mac;
+        let mut mac = hmac::Hmac::<sha2::Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(&wire);
+        mac.update(b"\x0aupdate-key\x00\x00\xff\x00\x00\x00\x00");
+        mac.update(b"\x0bhmac-sha256\x00\x00\x00\x65\x53\xf1\x00\x01\x2c\x00\x00\x00\x00");
+        let rdata = &tsig[22..];
+        assert_eq!(rdata[23..55], mac.finalize().into_bytes()[..]);
+        assert_eq!(rdata[55..], [0x12, 0x34, 0, 0, 0, 0]);
+        let other = TsigKey::new("update-key", b"other");
+        assert_ne!(update.to_signed_wire(0x1234, &other, now).unwrap(), signed);
+
+        let listener = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = listener.local_addr().unwrap();
+        let responder = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            for rcode in [0, 8] {
+                let mut buf = [0; 1024];
+                let (len, from) = listener.recv_from(&mut buf).unwrap();
+                received.push(buf[..len].to_vec());
+                let answer = [buf[0], buf[1], 0xa8, rcode, 0, 0, 0, 0, 0, 0, 0, 0];
+                listener.send_to(&answer, from).unwrap();
+            }
+            received
+        });
+        let timeout = std::time::Duration::from_secs(5);
+        update.send(server, 0x1234, Some(&key), timeout).unwrap();
+        assert!(update.send(server, 0x1234, None, timeout).unwrap_err().ends_with("with NXRRSET"));
+        let received = responder.join().unwrap();
+        assert_eq!(received[0][10..12], [0, 1]);
+        assert_eq!(received[1], wire);
+
+        let mut large = update.clone();
+        let txt = Txt { name: "big".to_string(), txt: vec!["\"a\""; 40000].join(" "), ttl: None };
+        large.operations.push(PatchOperation::Add { record: Record::Txt(txt) });
+        assert!(large.to_wire(1).unwrap_err().ends_with("is too long"));
+    }
+
     // ----- Parse Tests -----
     #[test]
     fn test_parse_global_info() {
@@ -200,7 +1199,7 @@ mod tests {
         let json1 = dns_zonefile.parse(&original_text).expect("correct!");
 
         // 3. Generate it back to text
-        let generated_text = dns_zonefile.generate(&json1, None);
+        let generated_text = dns_zonefile.generate(&json1, None).unwrap();
 
         // 4. Parse the generated text back to a JSON object (struct)
         let json2 = dns_zonefile.parse(&generated_text).expect("json");
@@ -210,8 +1209,355 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_empty_struct() {
         let dns_record = DnsRecord::default();
         assert_eq!(dns_record.is_empty(), true);
     }
+
+    fn get_parsed_zonemd_zone() -> DnsRecord {
+        let text = fs::read_to_string("tests/zonefile_zonemd.txt")
+            .expect("Failed to read zonefile_zonemd.txt");
+        DnsZonefile::default().parse(&text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_zonemd() {
+        let parsed = get_parsed_zonemd_zone();
+        let zonemd = &parsed.zonemd.as_ref().expect("zonemd")[0];
+        assert_eq!(zonemd.serial, 2018031900);
+        assert_eq!(zonemd.scheme, 1);
+        assert_eq!(zonemd.hash_algorithm, 1);
+        assert_eq!(zonemd.digest.len(), 48);
+        assert_eq!(parsed.verify_zonemd(), Ok(()));
+    }
+
+    #[test]
+    fn test_compute_zonemd() {
+        let mut zone = get_parsed_zonemd_zone();
+        let expected = zone.zonemd.take().unwrap().remove(0);
+        let computed = zone.compute_zonemd(ZonemdScheme::Simple, ZonemdHashAlgorithm::Sha384).unwrap();
+        assert_eq!(computed.digest, expected.digest);
+        assert_eq!(computed.serial, expected.serial);
+
+        let sha512 = zone.compute_zonemd(ZonemdScheme::Simple, ZonemdHashAlgorithm::Sha512).unwrap();
+        assert_eq!(sha512.digest.len(), 64);
+        zone.zonemd = Some(vec![sha512]);
+        assert_eq!(zone.verify_zonemd(), Ok(()));
+
+        zone.a.as_mut().unwrap()[0].ip = "203.0.113.64".to_string();
+        assert!(zone.verify_zonemd().is_err());
+    }
+
+    #[test]
+    fn test_zonemd_idempotence() {
+        let dns_zonefile = DnsZonefile::default();
+        let zone = get_parsed_zonemd_zone();
+        let generated = dns_zonefile.generate(&zone, None).unwrap();
+        let reparsed = dns_zonefile.parse(&generated).unwrap();
+        assert_eq!(reparsed.zonemd, zone.zonemd);
+        assert_eq!(reparsed.verify_zonemd(), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_typed_ds() {
+        let parsed = get_parsed_forward_zone();
+        let ds = parsed.ds.expect("ds");
+        assert_eq!(ds.len(), 2);
+        assert_eq!(ds[0], ds[1]);
+        assert_eq!(ds[0].key_tag, 60485);
+        assert_eq!(ds[0].algorithm, DnssecAlgorithm::RsaSha1);
+        assert_eq!(ds[0].digest_type, DigestType::Sha1);
+        assert_eq!(ds[0].digest.len(), 20);
+    }
+
+    #[test]
+    fn test_parse_malformed_ds() {
+        let dns_zonefile = DnsZonefile::default();
+        let text = r#"
+$ORIGIN example.
+@   NS  ns1.example.
+secure   DS  tag=12345 alg=3 digest_type=1 <foofoo>
+short    DS  60485 5 2 2BB183AF5F22588179A53B0A98631FAD1A292118
+good     DS  60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118
+"#;
+        assert!(dns_zonefile.parse_strict(text).is_err());
+        assert_eq!(dns_zonefile.parse(text).unwrap().ds.map(|ds| ds.len()), Some(3));
+
+        let (zone, warnings) = dns_zonefile.parse_lenient(text).unwrap();
+        let ds = zone.ds.as_ref().unwrap();
+        assert_eq!(ds.len(), 3);
+        assert_eq!(ds[0].raw.as_ref().unwrap().rdata, "tag=12345 alg=3 digest_type=1 <foofoo>");
+        assert!(ds[1].error().unwrap().contains("must be 32 octets"));
+        assert_eq!(ds[2].error(), None);
+        assert_eq!(zone.ns.as_ref().map(Vec::len), Some(1));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].record.contains("tag=12345"));
+        assert!(warnings[1].message.contains("must be 32 octets"));
+
+        let text = dns_zonefile.generate(&zone, None).unwrap();
+        assert!(text.contains("tag=12345 alg=3 digest_type=1 <foofoo>"));
+        assert!(zone.validate().findings.iter().any(|f| f.kind == FindingKind::InvalidRecord));
+    }
+
+    const DNSKEY_ZONE: &str = r#"
+$ORIGIN example.com.
+$TTL 86400
+dskey IN DNSKEY 256 3 5 ( AQOeiiR0GOMYkDshWoSKz9Xz
+                          fwJr1AYtsmx3TGkJaNXVbfi/
+                          2pHm822aJ5iI9BMzNXxeYCmZ
+                          DRD99WYwYqUSdjMmmAphXdvx
+                          egXd/M5+X7OrzKBaMbCVdFLU
+                          Uh6DhweJBjEVv5f2wwjM9Xzc
+                          nOf+EPbtG9DMBmADjFDc2w/r
+                          ljwvFw==
+                          ) ;  key id = 60485
+"#;
+
+    #[test]
+    fn test_dnskey_to_ds() {
+        let zone = DnsZonefile::default().parse(DNSKEY_ZONE).unwrap();
+        let key = &zone.dnskey.as_ref().expect("dnskey")[0];
+        assert_eq!(key.key_tag(), 60485);
+        assert!(key.is_zone_key());
+        assert!(!key.is_sep());
+
+        let sha1 = key.to_ds("example.com.", DigestType::Sha1).unwrap();
+        assert_eq!(sha1.name, "dskey.example.com.");
+        assert_eq!(sha1.digest, crate::encoding::hex_decode("2BB183AF5F22588179A53B0A98631FAD1A292118").unwrap());
+        let sha256 = key.to_ds("example.com.", DigestType::Sha256).unwrap();
+        assert_eq!(
+            sha256.digest,
+            crate::encoding::hex_decode("D4B7D520E7BB5F0F67674A0CCEB1E3E0614B93C4F9E99B8383F6A1E4469DA50A").unwrap()
+        );
+        assert_eq!(key.to_ds("example.com.", DigestType::Sha384).unwrap().digest.len(), 48);
+        assert!(key.to_ds("example.com.", DigestType::Gost).is_err());
+    }
+
+    #[test]
+    fn test_compute_ds_for_sep_keys() {
+        let dns_zonefile = DnsZonefile::default();
+        let mut zone = dns_zonefile.parse(DNSKEY_ZONE).unwrap();
+        assert_eq!(zone.compute_ds(&[DigestType::Sha256]), Ok(vec![]));
+
+        let mut ksk = zone.dnskey.as_ref().unwrap()[0].clone();
+        ksk.flags = 257;
+        zone.dnskey.as_mut().unwrap().push(ksk.clone());
+        let ds = zone.compute_ds(&[DigestType::Sha1, DigestType::Sha256]).unwrap();
+        assert_eq!(ds.len(), 2);
+        assert!(ds.iter().all(|d| d.key_tag == ksk.key_tag() && d.validate().is_ok()));
+
+        let parent = DnsRecord { origin: Some("com.".to_string()), ds: Some(ds), ..Default::default() };
+        let generated = dns_zonefile.generate(&parent, None).unwrap();
+        let reparsed = dns_zonefile.parse(&generated).unwrap();
+        assert_eq!(reparsed.ds, parent.ds);
+
+        let generated = dns_zonefile.generate(&zone, None).unwrap();
+        assert_eq!(dns_zonefile.parse(&generated).unwrap().dnskey, zone.dnskey);
+    }
+
+    #[cfg(feature = "dnssec")]
+    const SIGNING_ZONE: &str = "$ORIGIN example.com.
+$TTL 3600
+@ IN SOA ns1 hostmaster 2024010101 7200 3600 1209600 300
+@ IN NS ns1
+@ 3600 IN MX 10 mail.example.com.
+ns1 IN A 192.0.2.1
+sub IN NS ns.sub
+ns.sub IN A 192.0.2.2
+*.w IN TXT wildcard
+";
+
+    #[cfg(feature = "dnssec")]
+    fn signing_options(denial: super::Denial) -> super::SigningOptions {
+        super::SigningOptions {
+            inception: chrono::DateTime::from_timestamp(1438207200, 0).unwrap(),
+            expiration: chrono::DateTime::from_timestamp(1440021600, 0).unwrap(),
+            denial,
+        }
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_sign_ed25519() {
+        // RFC 8080 section 6.1
+        let key = super::SigningKey::from_bind_private(
+            "Private-key-format: v1.2\nAlgorithm: 15 (ED25519)\nPrivateKey: ODIyNjAzODQ2MjgwODAxMjI2NDUxOTAyMDQxNDIyNjI=\n",
+            257,
+        )
+        .unwrap();
+        let dnskey = key.dnskey("example.com.");
+        assert_eq!(crate::encoding::base64_encode(&dnskey.public_key), "l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=");
+        assert_eq!(dnskey.key_tag(), 3613);
+
+        let dns_zonefile = DnsZonefile::default();
+        let zone = dns_zonefile.parse(SIGNING_ZONE).unwrap();
+        let signed = zone.sign(std::slice::from_ref(&key), &signing_options(super::Denial::Nsec)).unwrap();
+        let rrsigs = signed.rrsig.as_ref().unwrap();
+        let mx = rrsigs.iter().find(|r| r.type_covered == super::RecordType::Mx).unwrap();
+        assert_eq!((mx.labels, mx.original_ttl, mx.key_tag), (2, 3600, 3613));
+        assert_eq!(
+            crate::encoding::base64_encode(&mx.signature),
+            "oL9krJun7xfBOIWcGHi7mag5/hdZrKWw15jPGrHpjQeRAvTdszaPD+QLs3fx8A4M3e23mRZ9VrbpMngwcrqNAg=="
+        );
+        assert_eq!(rrsigs.iter().find(|r| r.name == "*.w.example.com.").unwrap().labels, 3);
+        // delegation NS and glue stay unsigned
+        assert!(!rrsigs.iter().any(|r| r.name == "ns.sub.example.com."));
+        assert!(!rrsigs.iter().any(|r| r.name == "sub.example.com." && r.type_covered == super::RecordType::Ns));
+
+        // the NSEC chain visits every authoritative name and closes at the apex
+        let nsec = signed.nsec.as_ref().unwrap();
+        let names: Vec<(&str, &str)> = nsec.iter().map(|n| (n.name.as_str(), n.next_domain.as_str())).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("example.com.", "ns1.example.com."),
+                ("ns1.example.com.", "sub.example.com."),
+                ("sub.example.com.", "*.w.example.com."),
+                ("*.w.example.com.", "example.com."),
+            ]
+        );
+        assert_eq!(nsec[0].ttl, Some(300));
+
+        let generated = dns_zonefile.generate(&signed, None).unwrap();
+        assert_eq!(dns_zonefile.parse(&generated).unwrap().dnskey, signed.dnskey);
+        // signing again replaces the previous signatures
+        let resigned = signed.sign(&[key], &signing_options(super::Denial::Nsec)).unwrap();
+        assert_eq!(resigned.rrsig, signed.rrsig);
+        assert_eq!(resigned.dnskey, signed.dnskey);
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_sign_ecdsa_nsec3() {
+        use p256::ecdsa::signature::Verifier;
+
+        // RFC 6605 section 6.1
+        let key = super::SigningKey::from_bind_private(
+            "Private-key-format: v1.2\nAlgorithm: 13 (ECDSAP256SHA256)\nPrivateKey: GU6SnQ/Ou+xC5RumuIUIuJZteXT2z0O/ok1s38Et6mQ=\n",
+            257,
+        )
+        .unwrap();
+        assert_eq!(
+            crate::encoding::base64_encode(&key.public_key()),
+            "GojIhhXUN/u4v54ZQqGSnyhWJwaubCvTmeexv7bR6edbkrSqQpF64cYbcB7wNcP+e+MAnLr+Wi9xMWyQLc8NAA=="
+        );
+
+        let zone = DnsZonefile::default().parse(SIGNING_ZONE).unwrap();
+        let denial = super::Denial::Nsec3 { iterations: 12, salt: vec![0xaa, 0xbb, 0xcc, 0xdd] };
+        let signed = zone.sign(&[key], &signing_options(denial)).unwrap();
+        assert!(signed.nsec.is_none());
+        assert_eq!(signed.nsec3param.as_ref().unwrap()[0].iterations, 12);
+
+        // the hashed chain is closed: every next hash is the owner of another record
+        let nsec3 = signed.nsec3.as_ref().unwrap();
+        // apex, ns1, sub, *.w and the empty non-terminal w
+        assert_eq!(nsec3.len(), 5);
+        for n in nsec3 {
+            let next = crate::encoding::base32hex_encode(&n.next_hashed).to_ascii_lowercase();
+            assert!(nsec3.iter().any(|o| o.name == format!("{next}.example.com.")));
+        }
+
+        let mut public_key = vec![4];
+        public_key.extend(&signed.dnskey.as_ref().unwrap()[0].public_key);
+        let verifying_key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&public_key).unwrap();
+        let rrsets = signed.rrsets().unwrap();
+        let rrsigs = signed.rrsig.as_ref().unwrap();
+        for rrsig in rrsigs {
+            let rrset = rrsets
+                .iter()
+                .find(|r| r.rtype == rrsig.type_covered && r.name.eq_ignore_ascii_case(&rrsig.name))
+                .unwrap();
+            let data = crate::dnssec::signed_data(rrsig, rrset).unwrap();
+            let signature = p256::ecdsa::Signature::from_slice(&rrsig.signature).unwrap();
+            assert!(verifying_key.verify(&data, &signature).is_ok(), "{rrsig:?}");
+        }
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_sign_rsa_ksk_zsk() {
+        let ksk = super::SigningKey::from_pkcs8_pem(&fs::read_to_string("tests/ksk_rsasha256.pem").unwrap(), 257).unwrap();
+        assert_eq!(ksk.algorithm(), DnssecAlgorithm::RsaSha256);
+        let zsk = super::SigningKey::from_bind_private(
+            "Private-key-format: v1.2\nAlgorithm: 15 (ED25519)\nPrivateKey: ODIyNjAzODQ2MjgwODAxMjI2NDUxOTAyMDQxNDIyNjI=\n",
+            256,
+        )
+        .unwrap();
+        let ksk_tag = ksk.dnskey("example.com.").key_tag();
+
+        let zone = DnsZonefile::default().parse(SIGNING_ZONE).unwrap();
+        let signed = zone.sign(&[ksk, zsk], &signing_options(super::Denial::Nsec)).unwrap();
+        assert_eq!(signed.dnskey.as_ref().unwrap().len(), 2);
+        for rrsig in signed.rrsig.as_ref().unwrap() {
+            let by_ksk = rrsig.key_tag == ksk_tag;
+            assert_eq!(by_ksk, rrsig.type_covered == super::RecordType::Dnskey);
+        }
+        assert_eq!(signed.compute_ds(&[DigestType::Sha256]).unwrap()[0].key_tag, ksk_tag);
+        assert!(super::SigningKey::from_pkcs8_pem("not a key", 257).is_err());
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_verify_dnssec() {
+        use super::RrsetStatus;
+
+        let key = super::SigningKey::from_bind_private(
+            "Private-key-format: v1.2\nAlgorithm: 15 (ED25519)\nPrivateKey: ODIyNjAzODQ2MjgwODAxMjI2NDUxOTAyMDQxNDIyNjI=\n",
+            257,
+        )
+        .unwrap();
+        let dns_zonefile = DnsZonefile::default();
+        let zone = dns_zonefile.parse(SIGNING_ZONE).unwrap();
+        let now = chrono::DateTime::from_timestamp(1439000000, 0).unwrap();
+        assert!(!zone.verify_dnssec(now).unwrap().is_valid());
+
+        let child_key = super::SigningKey::from_bind_private(
+            "Private-key-format: v1.2\nAlgorithm: 15 (ED25519)\nPrivateKey: MTIzNDU2Nzg5MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTI=\n",
+            257,
+        )
+        .unwrap()
+        .dnskey("sub.example.com.");
+        let mut sub_ds = child_key.to_ds("example.com.", DigestType::Sha256).unwrap();
+        let mut zone_with_ds = zone.clone();
+        zone_with_ds.ds = Some(vec![sub_ds.clone()]);
+        for denial in [super::Denial::Nsec, super::Denial::Nsec3 { iterations: 0, salt: vec![] }] {
+            let signed = zone_with_ds.sign(std::slice::from_ref(&key), &signing_options(denial)).unwrap();
+            // RRSIG, NSEC and NSEC3 records survive a round trip through the zone file
+            let parsed = dns_zonefile.parse(&dns_zonefile.generate(&signed, None).unwrap()).unwrap();
+            assert_eq!((&parsed.rrsig, &parsed.nsec, &parsed.nsec3), (&signed.rrsig, &signed.nsec, &signed.nsec3));
+            assert_eq!(parsed.nsec3param, signed.nsec3param);
+            let report = parsed.verify_dnssec_with(now, std::slice::from_ref(&child_key)).unwrap();
+            assert!(report.is_valid(), "{report:?}");
+            let status = |report: &super::DnssecReport, name: &str, rtype: super::RecordType| {
+                report.rrsets.iter().find(|r| r.name == name && r.rtype == rtype).unwrap().status.clone()
+            };
+            assert_eq!(status(&report, "sub.example.com.", super::RecordType::Ns), RrsetStatus::Unsigned);
+            assert_eq!(status(&report, "ns.sub.example.com.", super::RecordType::A), RrsetStatus::Unsigned);
+            assert_eq!(status(&report, "sub.example.com.", super::RecordType::Ds), RrsetStatus::Valid { key_tags: vec![3613] });
+            assert_eq!(status(&report, "example.com.", super::RecordType::Dnskey), RrsetStatus::Valid { key_tags: vec![3613] });
+            // without the child keys the DS records can not be checked
+            let report = parsed.verify_dnssec(now).unwrap();
+            assert_eq!(status(&report, "sub.example.com.", super::RecordType::Ds), RrsetStatus::Unverified { key_tags: vec![3613] });
+        }
+
+        let signed = zone.sign(&[key], &signing_options(super::Denial::Nsec)).unwrap();
+        let later = chrono::DateTime::from_timestamp(1440021601, 0).unwrap();
+        let report = signed.verify_dnssec(later).unwrap();
+        assert!(report.rrsets.iter().all(|r| matches!(&r.status, RrsetStatus::Invalid(e) if e.starts_with("RRSIG expired"))
+            || r.status == RrsetStatus::Unsigned));
+
+        let mut tampered = signed.clone();
+        tampered.mx.as_mut().unwrap()[0].preference = 20;
+        tampered.nsec.as_mut().unwrap().remove(1);
+        sub_ds.digest[0] ^= 0xff;
+        tampered.ds = Some(vec![sub_ds.clone()]);
+        let report = tampered.verify_dnssec_with(now, &[child_key]).unwrap();
+        let mx = report.rrsets.iter().find(|r| r.rtype == super::RecordType::Mx).unwrap();
+        assert_eq!(mx.status, RrsetStatus::Invalid("bad signature".to_string()));
+        // the DS RRset is not signed and matches no key
+        assert!(report.rrsets.iter().any(|r| r.rtype == super::RecordType::Ds && r.status == RrsetStatus::Missing));
+        assert!(report.errors.contains(&"ns1.example.com. has no NSEC record".to_string()));
+        assert!(report.errors.iter().any(|e| e.starts_with(&format!("DS {} at sub.example.com. matches no DNSKEY", sub_ds.key_tag))));
+    }
 }
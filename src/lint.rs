@@ -0,0 +1,382 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::check::Severity;
+use crate::dns_structs::*;
+use crate::name;
+use crate::record::Record;
+
+/// a zone policy, like a minimum TTL or a CAA record at the apex
+pub trait LintRule {
+    /// stable identifier used in `LintConfig` and suppression comments, like `min-ttl`
+    fn id(&self) -> &'static str;
+    /// one line of what the rule enforces
+    fn description(&self) -> &'static str;
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+    /// violations of the rule in `zone`, `config` holds the rule options
+    fn check(&self, zone: &DnsRecord, config: &RuleConfig) -> Vec<Violation>;
+}
+
+/// where a rule is broken, the engine adds the rule and severity
+#[derive(Debug, PartialEq, Clone)]
+pub struct Violation {
+    /// absolute owner name
+    pub name: String,
+    pub rtype: RecordType,
+    pub message: String,
+}
+
+/// violation of a rule with the severity it is configured with
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct LintFinding {
+    pub rule: String,
+    pub severity: Severity,
+    pub name: String,
+    pub rtype: RecordType,
+    pub message: String,
+}
+
+impl Display for LintFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}: {} [{}]", self.name, self.rtype, self.message, self.rule)
+    }
+}
+
+/// value of a rule option, numbers, flags, text or lists of text
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="serde", serde(untagged))]
+pub enum OptionValue {
+    Flag(bool),
+    Number(u64),
+    Text(String),
+    List(Vec<String>),
+}
+
+/// settings of one rule, every other key is an option of the rule
+///
+/// ```toml
+/// [rules.min-ttl]
+/// severity = "error"
+/// min = 600
+/// ```
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct RuleConfig {
+    /// `None` leaves the rule enabled
+    #[cfg_attr(feature="serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub enabled: Option<bool>,
+    /// `None` is the default severity of the rule
+    #[cfg_attr(feature="serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub severity: Option<Severity>,
+    #[cfg_attr(feature="serde", serde(flatten))]
+    pub options: BTreeMap<String, OptionValue>,
+}
+
+impl RuleConfig {
+    pub fn number(&self, key: &str) -> Option<u64> {
+        match self.options.get(key)? {
+            OptionValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn flag(&self, key: &str) -> Option<bool> {
+        match self.options.get(key)? {
+            OptionValue::Flag(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn list(&self, key: &str) -> Option<&[String]> {
+        match self.options.get(key)? {
+            OptionValue::List(l) => Some(l),
+            _ => None,
+        }
+    }
+}
+
+/// rule settings by rule id, read from TOML or JSON with serde
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct LintConfig {
+    #[cfg_attr(feature="serde", serde(default))]
+    pub rules: BTreeMap<String, RuleConfig>,
+}
+
+/// runs the enabled rules over zones
+pub struct Linter {
+    rules: Vec<Box<dyn LintRule>>,
+    config: LintConfig,
+}
+
+impl Linter {
+    /// linter with the built-in rules and `config`
+    pub fn new(config: LintConfig) -> Self {
+        Self { rules: builtin_rules(), config }
+    }
+
+    /// add a rule of your own next to the built-in ones
+    pub fn with_rule(mut self, rule: Box<dyn LintRule>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &dyn LintRule> {
+        self.rules.iter().map(|r| r.as_ref())
+    }
+
+    /// findings of every enabled rule, a config entry for an unknown rule is an error
+    pub fn lint(&self, zone: &DnsRecord) -> Result<Vec<LintFinding>, String> {
+        self.lint_suppressed(zone, &Suppressions::default())
+    }
+
+    /// lint like `lint` and drop the findings `suppressions` silence
+    pub fn lint_suppressed(&self, zone: &DnsRecord, suppressions: &Suppressions) -> Result<Vec<LintFinding>, String> {
+        if let Some(id) = self.config.rules.keys().find(|id| !self.rules.iter().any(|r| r.id() == id.as_str())) {
+            return Err(format!("unknown lint rule `{id}`"));
+        }
+        let default = RuleConfig::default();
+        let mut ret = Vec::new();
+        for rule in &self.rules {
+            let config = self.config.rules.get(rule.id()).unwrap_or(&default);
+            if config.enabled == Some(false) {
+                continue;
+            }
+            let severity = config.severity.unwrap_or_else(|| rule.default_severity());
+            ret.extend(
+                rule.check(zone, config)
+                    .into_iter()
+                    .filter(|v| !suppressions.is_suppressed(rule.id(), &v.name))
+                    .map(|v| LintFinding { rule: rule.id().to_string(), severity, name: v.name, rtype: v.rtype, message: v.message }),
+            );
+        }
+        Ok(ret)
+    }
+}
+
+/// rules silenced by `; lint:ignore` comments in a zone file
+///
+/// `; lint:ignore min-ttl, require-caa` after a record silences the rules for
+/// its owner name, on a line of its own for the owner of the next record.
+/// `; lint:ignore-file min-ttl` silences the rules for the whole zone,
+/// `all` stands for every rule
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Suppressions {
+    file: Vec<String>,
+    /// rule ids by lower-case absolute owner name
+    owners: BTreeMap<String, Vec<String>>,
+}
+
+impl Suppressions {
+    /// read the comments of a zone file, relative owners are made absolute
+    /// with `$ORIGIN`, `origin` before the first one
+    pub fn from_zonefile(text: &str, origin: &str) -> Self {
+        let mut ret = Self::default();
+        let mut origin = name::absolute_origin(origin);
+        let mut owner = origin.clone();
+        let mut pending: Vec<String> = Vec::new();
+        let mut depth = 0i32;
+        for line in text.lines() {
+            let (data, comment) = split_comment(line);
+            let continuation = depth > 0;
+            depth += data.matches('(').count() as i32 - data.matches(')').count() as i32;
+            let has_record = !data.trim().is_empty();
+            if let Some(first) = data.split_whitespace().next().filter(|_| !continuation) {
+                if first.eq_ignore_ascii_case("$ORIGIN") {
+                    if let Some(o) = data.split_whitespace().nth(1) {
+                        origin = name::fqdn(o, &origin);
+                    }
+                    continue;
+                }
+                if first.starts_with('$') {
+                    continue;
+                }
+                if !line.starts_with(char::is_whitespace) {
+                    owner = name::fqdn(first, &origin).to_ascii_lowercase();
+                }
+            }
+            let rules = comment.map(parse_directive).unwrap_or_default();
+            match rules {
+                Some((true, rules)) => ret.file.extend(rules),
+                Some((false, rules)) if has_record => ret.owners.entry(owner.clone()).or_default().extend(rules),
+                Some((false, rules)) => pending.extend(rules),
+                None => {}
+            }
+            if has_record && !continuation && !pending.is_empty() {
+                ret.owners.entry(owner.clone()).or_default().append(&mut pending);
+            }
+        }
+        ret
+    }
+
+    pub fn is_suppressed(&self, rule: &str, owner: &str) -> bool {
+        let matches = |r: &String| r == rule || r == "all";
+        self.file.iter().any(matches)
+            || self.owners.get(&owner.to_ascii_lowercase()).is_some_and(|rules| rules.iter().any(matches))
+    }
+}
+
+/// the line before the first `;` outside quotes and the comment after it
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => return (&line[..i], Some(&line[i + 1..])),
+            _ => {}
+        }
+    }
+    (line, None)
+}
+
+/// rule ids of a `lint:ignore` or `lint:ignore-file` comment, true for the whole file
+fn parse_directive(comment: &str) -> Option<(bool, Vec<String>)> {
+    let comment = comment.trim();
+    let (file, rules) = if let Some(rules) = comment.strip_prefix("lint:ignore-file") {
+        (true, rules)
+    } else {
+        (false, comment.strip_prefix("lint:ignore")?)
+    };
+    let rules = rules.split([',', ' ', '\t']).filter(|r| !r.is_empty()).map(str::to_string).collect();
+    Some((file, rules))
+}
+
+/// the rules every `Linter` starts with
+pub fn builtin_rules() -> Vec<Box<dyn LintRule>> {
+    vec![Box::new(MinTtl), Box::new(RequireCaa), Box::new(NoWildcardMx), Box::new(HostnameSyntax)]
+}
+
+/// TTLs below `min` seconds, 300 by default, make caches query too often
+pub struct MinTtl;
+
+impl LintRule for MinTtl {
+    fn id(&self) -> &'static str {
+        "min-ttl"
+    }
+
+    fn description(&self) -> &'static str {
+        "records need a TTL of at least `min` seconds"
+    }
+
+    fn check(&self, zone: &DnsRecord, config: &RuleConfig) -> Vec<Violation> {
+        let min = config.number("min").unwrap_or(300);
+        let apex = zone.zone_origin();
+        zone.records()
+            .iter()
+            .filter(|r| u64::from(zone.effective_ttl(r.ttl())) < min)
+            .map(|r| Violation {
+                name: name::fqdn(r.name(), &apex),
+                rtype: r.record_type(),
+                message: format!("TTL {} is below {min}", zone.effective_ttl(r.ttl())),
+            })
+            .collect()
+    }
+}
+
+/// a CAA record at the apex limits which CAs may issue certificates
+pub struct RequireCaa;
+
+impl LintRule for RequireCaa {
+    fn id(&self) -> &'static str {
+        "require-caa"
+    }
+
+    fn description(&self) -> &'static str {
+        "the zone apex needs a CAA record"
+    }
+
+    fn check(&self, zone: &DnsRecord, _config: &RuleConfig) -> Vec<Violation> {
+        let apex = zone.zone_origin();
+        let has_caa = zone.caa.iter().flatten().any(|c| name::canonical_cmp(&name::fqdn(&c.name, &apex), &apex).is_eq());
+        if has_caa {
+            return Vec::new();
+        }
+        vec![Violation { name: apex, rtype: RecordType::Caa, message: "no CAA record at the apex".to_string() }]
+    }
+}
+
+/// wildcard MX records accept mail for names nobody meant to exist
+pub struct NoWildcardMx;
+
+impl LintRule for NoWildcardMx {
+    fn id(&self) -> &'static str {
+        "no-wildcard-mx"
+    }
+
+    fn description(&self) -> &'static str {
+        "MX records can not have a wildcard owner"
+    }
+
+    fn check(&self, zone: &DnsRecord, _config: &RuleConfig) -> Vec<Violation> {
+        let apex = zone.zone_origin();
+        zone.mx
+            .iter()
+            .flatten()
+            .filter(|mx| mx.name == "*" || mx.name.starts_with("*."))
+            .map(|mx| Violation {
+                name: name::fqdn(&mx.name, &apex),
+                rtype: RecordType::Mx,
+                message: "wildcard MX record".to_string(),
+            })
+            .collect()
+    }
+}
+
+/// owners of address records are host names, letters, digits and hyphens
+/// (RFC 1123), `allow_underscore` also accepts underscores
+pub struct HostnameSyntax;
+
+impl LintRule for HostnameSyntax {
+    fn id(&self) -> &'static str {
+        "hostname-syntax"
+    }
+
+    fn description(&self) -> &'static str {
+        "A and AAAA owners need valid host names"
+    }
+
+    fn check(&self, zone: &DnsRecord, config: &RuleConfig) -> Vec<Violation> {
+        let allow_underscore = config.flag("allow_underscore").unwrap_or(false);
+        let valid = |label: &str| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || (allow_underscore && b == b'_'))
+        };
+        let apex = zone.zone_origin();
+        zone.records()
+            .iter()
+            .filter(|r| matches!(r, Record::A(_) | Record::Aaaa(_)))
+            .filter_map(|r| {
+                let owner = name::fqdn(r.name(), &apex);
+                // only the labels the zone adds are checked
+                let relative = name::relative(&owner, &apex);
+                let relative = relative.strip_suffix('.').unwrap_or(&relative);
+                if relative == "@" {
+                    return None;
+                }
+                let mut labels = relative.split('.');
+                if relative.starts_with("*.") || relative == "*" {
+                    labels.next();
+                }
+                let bad: Vec<&str> = labels.filter(|l| !valid(l)).collect();
+                (!bad.is_empty()).then(|| Violation {
+                    name: owner,
+                    rtype: r.record_type(),
+                    message: format!("`{}` is not a valid host name label", bad.join("`, `")),
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,484 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::dns_structs::*;
+use crate::encoding::base64_decode;
+use crate::name;
+use crate::record::{character_strings, joined_text};
+
+/// shortest RSA key DKIM verifiers accept (RFC 8301 section 3.2)
+pub const DKIM_MIN_RSA_BITS: usize = 1024;
+
+/// `tag=value` pairs separated by `;` (RFC 6376 section 3.2), the first tag has to be `version`
+fn tag_list<'a>(text: &'a str, version: Option<(&str, &str)>) -> Result<Vec<(&'a str, &'a str)>, String> {
+    let mut ret: Vec<(&str, &str)> = Vec::new();
+    for spec in text.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        let (tag, value) = spec.split_once('=').ok_or_else(|| format!("`{spec}` is not a tag=value pair"))?;
+        let (tag, value) = (tag.trim(), value.trim());
+        let valid = tag.starts_with(|c: char| c.is_ascii_alphabetic()) && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!("invalid tag `{tag}`"));
+        }
+        if ret.iter().any(|(t, _)| *t == tag) {
+            return Err(format!("tag `{tag}` appears more than once"));
+        }
+        ret.push((tag, value));
+    }
+    if let Some((tag, expected)) = version {
+        match ret.first() {
+            Some((t, v)) if *t == tag && *v == expected => {}
+            _ => return Err(format!("record has to start with `{tag}={expected}`")),
+        }
+    }
+    Ok(ret)
+}
+
+fn tag<'a>(tags: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    tags.iter().find(|(t, _)| *t == name).map(|(_, v)| *v)
+}
+
+/// comma separated report addresses, each a URI like `mailto:` or `https:`
+fn uris(value: &str, schemes: &[&str], tag: &str) -> Result<Vec<String>, String> {
+    let mut ret = Vec::new();
+    for uri in value.split(',').map(str::trim) {
+        let scheme = uri.split_once(':').map(|(s, _)| s.to_ascii_lowercase());
+        if !scheme.is_some_and(|s| schemes.contains(&s.as_str())) {
+            return Err(format!("`{uri}` in `{tag}` is not a {} URI", schemes.join(" or ")));
+        }
+        ret.push(uri.to_string());
+    }
+    Ok(ret)
+}
+
+/// the record as a TXT record at `name`, checked by parsing it back
+fn to_txt<T: FromStr<Err = String> + Display>(record: &T, name: &str) -> Result<Txt, String> {
+    let text = record.to_string();
+    text.parse::<T>()?;
+    Ok(Txt { name: name.to_string(), txt: character_strings(&text)?.join(" "), ttl: None })
+}
+
+/// what a DMARC receiver does with mail that fails the checks
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DmarcPolicy {
+    None,
+    Quarantine,
+    Reject,
+}
+
+impl FromStr for DmarcPolicy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(DmarcPolicy::None),
+            "quarantine" => Ok(DmarcPolicy::Quarantine),
+            "reject" => Ok(DmarcPolicy::Reject),
+            _ => Err(format!("unknown DMARC policy `{s}`")),
+        }
+    }
+}
+
+impl Display for DmarcPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DmarcPolicy::None => write!(f, "none"),
+            DmarcPolicy::Quarantine => write!(f, "quarantine"),
+            DmarcPolicy::Reject => write!(f, "reject"),
+        }
+    }
+}
+
+/// DKIM and SPF identifier alignment
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Alignment {
+    Relaxed,
+    Strict,
+}
+
+impl FromStr for Alignment {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "r" => Ok(Alignment::Relaxed),
+            "s" => Ok(Alignment::Strict),
+            _ => Err(format!("alignment `{s}` is neither `r` nor `s`")),
+        }
+    }
+}
+
+impl Display for Alignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", if *self == Alignment::Relaxed { "r" } else { "s" })
+    }
+}
+
+/// DMARC policy published at `_dmarc.<domain>` (RFC 7489)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Dmarc {
+    /// `p`
+    pub policy: DmarcPolicy,
+    /// `sp`, the policy of subdomains
+    pub subdomain_policy: Option<DmarcPolicy>,
+    /// `pct`, share of failing mail the policy applies to
+    pub percent: Option<u8>,
+    /// `rua`, where aggregate reports go
+    pub rua: Vec<String>,
+    /// `ruf`, where failure reports go
+    pub ruf: Vec<String>,
+    pub adkim: Option<Alignment>,
+    pub aspf: Option<Alignment>,
+    /// `fo`, when failure reports are sent
+    pub failure_options: Option<String>,
+    /// `ri`, seconds between aggregate reports
+    pub report_interval: Option<u32>,
+}
+
+impl Dmarc {
+    pub fn new(policy: DmarcPolicy) -> Self {
+        Self {
+            policy,
+            subdomain_policy: None,
+            percent: None,
+            rua: Vec::new(),
+            ruf: Vec::new(),
+            adkim: None,
+            aspf: None,
+            failure_options: None,
+            report_interval: None,
+        }
+    }
+
+    /// TXT record for `name`, usually `_dmarc`
+    pub fn to_txt(&self, name: &str) -> Result<Txt, String> {
+        to_txt(self, name)
+    }
+}
+
+impl FromStr for Dmarc {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tags = tag_list(s, Some(("v", "DMARC1")))?;
+        let policy = tag(&tags, "p").ok_or("DMARC record needs a `p` tag")?.parse()?;
+        let mut ret = Self::new(policy);
+        ret.subdomain_policy = tag(&tags, "sp").map(str::parse).transpose()?;
+        ret.percent = match tag(&tags, "pct") {
+            Some(pct) => match pct.parse() {
+                Ok(pct) if pct <= 100 => Some(pct),
+                _ => return Err(format!("`pct={pct}` is not a percentage")),
+            },
+            None => None,
+        };
+        let report_uri = ["mailto", "https", "http"];
+        ret.rua = tag(&tags, "rua").map(|v| uris(v, &report_uri, "rua")).transpose()?.unwrap_or_default();
+        ret.ruf = tag(&tags, "ruf").map(|v| uris(v, &report_uri, "ruf")).transpose()?.unwrap_or_default();
+        ret.adkim = tag(&tags, "adkim").map(str::parse).transpose()?;
+        ret.aspf = tag(&tags, "aspf").map(str::parse).transpose()?;
+        if let Some(fo) = tag(&tags, "fo") {
+            if !fo.split(':').all(|o| matches!(o.trim(), "0" | "1" | "d" | "s")) {
+                return Err(format!("`fo={fo}` has options other than 0, 1, d and s"));
+            }
+            ret.failure_options = Some(fo.to_string());
+        }
+        ret.report_interval = tag(&tags, "ri")
+            .map(|ri| ri.parse().map_err(|_| format!("`ri={ri}` is not a number of seconds")))
+            .transpose()?;
+        Ok(ret)
+    }
+}
+
+impl Display for Dmarc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v=DMARC1; p={}", self.policy)?;
+        if let Some(sp) = self.subdomain_policy {
+            write!(f, "; sp={sp}")?;
+        }
+        if let Some(pct) = self.percent {
+            write!(f, "; pct={pct}")?;
+        }
+        if !self.rua.is_empty() {
+            write!(f, "; rua={}", self.rua.join(","))?;
+        }
+        if !self.ruf.is_empty() {
+            write!(f, "; ruf={}", self.ruf.join(","))?;
+        }
+        if let Some(adkim) = self.adkim {
+            write!(f, "; adkim={adkim}")?;
+        }
+        if let Some(aspf) = self.aspf {
+            write!(f, "; aspf={aspf}")?;
+        }
+        if let Some(fo) = &self.failure_options {
+            write!(f, "; fo={fo}")?;
+        }
+        if let Some(ri) = self.report_interval {
+            write!(f, "; ri={ri}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DkimKeyType {
+    Rsa,
+    Ed25519,
+}
+
+/// DKIM public key published at `<selector>._domainkey.<domain>` (RFC 6376)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Dkim {
+    /// `k`, RSA when left out
+    pub key_type: DkimKeyType,
+    /// `p`, base64 of the key, empty for a revoked key
+    pub public_key: String,
+    /// `h`, hash algorithms the key may be used with
+    pub hash_algorithms: Vec<String>,
+    /// `s`, service types
+    pub services: Vec<String>,
+    /// `t`, like `y` for testing or `s` for no subdomains
+    pub flags: Vec<String>,
+    /// `n`
+    pub notes: Option<String>,
+}
+
+impl Dkim {
+    pub fn new(key_type: DkimKeyType, public_key: &str) -> Self {
+        Self {
+            key_type,
+            public_key: public_key.to_string(),
+            hash_algorithms: Vec::new(),
+            services: Vec::new(),
+            flags: Vec::new(),
+            notes: None,
+        }
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.public_key.is_empty()
+    }
+
+    /// length of the RSA modulus, `None` for other key types and revoked keys
+    pub fn key_bits(&self) -> Option<usize> {
+        if self.key_type != DkimKeyType::Rsa || self.is_revoked() {
+            return None;
+        }
+        rsa_bits(&base64_decode(&self.public_key).ok()?)
+    }
+
+    /// TXT record for `name`, usually `<selector>._domainkey`
+    pub fn to_txt(&self, name: &str) -> Result<Txt, String> {
+        to_txt(self, name)
+    }
+}
+
+/// DER element: tag, content and what follows it
+fn der(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, data) = data.split_first()?;
+    let (&first, mut data) = data.split_first()?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 || data.len() < n {
+            return None;
+        }
+        let len = data[..n].iter().fold(0usize, |acc, b| acc << 8 | *b as usize);
+        data = &data[n..];
+        len
+    };
+    (data.len() >= len).then(|| (tag, &data[..len], &data[len..]))
+}
+
+/// modulus length of an RSA key as SubjectPublicKeyInfo or PKCS#1 RSAPublicKey
+fn rsa_bits(key: &[u8]) -> Option<usize> {
+    let (0x30, sequence, _) = der(key)? else { return None };
+    let (tag, first, rest) = der(sequence)?;
+    let modulus = match tag {
+        0x02 => first,
+        0x30 => {
+            let (0x03, bits, _) = der(rest)? else { return None };
+            let (0x30, pkcs1, _) = der(bits.get(1..)?)? else { return None };
+            let (0x02, modulus, _) = der(pkcs1)? else { return None };
+            modulus
+        }
+        _ => return None,
+    };
+    let start = modulus.iter().position(|b| *b != 0)?;
+    Some((modulus.len() - start) * 8 - modulus[start].leading_zeros() as usize)
+}
+
+impl FromStr for Dkim {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tags = tag_list(s, None)?;
+        if tag(&tags, "v").is_some_and(|v| tags[0].0 != "v" || v != "DKIM1") {
+            return Err("`v=DKIM1` has to be the first tag".to_string());
+        }
+        let key_type = match tag(&tags, "k").unwrap_or("rsa") {
+            "rsa" => DkimKeyType::Rsa,
+            "ed25519" => DkimKeyType::Ed25519,
+            k => return Err(format!("unknown DKIM key type `{k}`")),
+        };
+        let public_key: String = tag(&tags, "p").ok_or("DKIM record needs a `p` tag")?.split_whitespace().collect();
+        let list = |name: &str| -> Vec<String> {
+            tag(&tags, name).map(|v| v.split(':').map(|s| s.trim().to_string()).collect()).unwrap_or_default()
+        };
+        let ret = Self {
+            key_type,
+            hash_algorithms: list("h"),
+            services: list("s"),
+            flags: list("t"),
+            notes: tag(&tags, "n").map(str::to_string),
+            public_key,
+        };
+        if ret.is_revoked() {
+            return Ok(ret);
+        }
+        let key = base64_decode(&ret.public_key).map_err(|_| "`p` is not base64".to_string())?;
+        match key_type {
+            DkimKeyType::Rsa => match rsa_bits(&key) {
+                Some(bits) if bits < DKIM_MIN_RSA_BITS => {
+                    return Err(format!("RSA key of {bits} bits, at least {DKIM_MIN_RSA_BITS} are required"));
+                }
+                Some(_) => {}
+                None => return Err("`p` is not an RSA public key".to_string()),
+            },
+            DkimKeyType::Ed25519 if key.len() != 32 => {
+                return Err(format!("Ed25519 key of {} octets, it has to be 32", key.len()));
+            }
+            DkimKeyType::Ed25519 => {}
+        }
+        Ok(ret)
+    }
+}
+
+impl Display for Dkim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v=DKIM1")?;
+        if self.key_type == DkimKeyType::Ed25519 {
+            write!(f, "; k=ed25519")?;
+        }
+        if !self.hash_algorithms.is_empty() {
+            write!(f, "; h={}", self.hash_algorithms.join(":"))?;
+        }
+        if !self.services.is_empty() {
+            write!(f, "; s={}", self.services.join(":"))?;
+        }
+        if !self.flags.is_empty() {
+            write!(f, "; t={}", self.flags.join(":"))?;
+        }
+        if let Some(n) = &self.notes {
+            write!(f, "; n={n}")?;
+        }
+        write!(f, "; p={}", self.public_key)
+    }
+}
+
+/// MTA-STS policy announcement at `_mta-sts.<domain>` (RFC 8461)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MtaSts {
+    /// changes whenever the policy file does
+    pub id: String,
+}
+
+impl MtaSts {
+    pub fn new(id: &str) -> Self {
+        Self { id: id.to_string() }
+    }
+
+    /// TXT record for `name`, usually `_mta-sts`
+    pub fn to_txt(&self, name: &str) -> Result<Txt, String> {
+        to_txt(self, name)
+    }
+}
+
+impl FromStr for MtaSts {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tags = tag_list(s, Some(("v", "STSv1")))?;
+        let id = tag(&tags, "id").ok_or("MTA-STS record needs an `id` tag")?;
+        if id.is_empty() || id.len() > 32 || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!("`id={id}` has to be 1 to 32 letters and digits"));
+        }
+        Ok(Self::new(id))
+    }
+}
+
+impl Display for MtaSts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v=STSv1; id={}", self.id)
+    }
+}
+
+/// SMTP TLS reporting at `_smtp._tls.<domain>` (RFC 8460)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TlsRpt {
+    /// `mailto:` or `https:` addresses reports go to
+    pub rua: Vec<String>,
+}
+
+impl TlsRpt {
+    pub fn new(rua: &[&str]) -> Self {
+        Self { rua: rua.iter().map(|s| s.to_string()).collect() }
+    }
+
+    /// TXT record for `name`, usually `_smtp._tls`
+    pub fn to_txt(&self, name: &str) -> Result<Txt, String> {
+        to_txt(self, name)
+    }
+}
+
+impl FromStr for TlsRpt {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tags = tag_list(s, Some(("v", "TLSRPTv1")))?;
+        let rua = tag(&tags, "rua").ok_or("TLS-RPT record needs a `rua` tag")?;
+        Ok(Self { rua: uris(rua, &["mailto", "https"], "rua")? })
+    }
+}
+
+impl Display for TlsRpt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v=TLSRPTv1; rua={}", self.rua.join(","))
+    }
+}
+
+/// a mail policy TXT record, told apart by its owner name
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MailPolicy {
+    Dmarc(Dmarc),
+    Dkim(Dkim),
+    MtaSts(MtaSts),
+    TlsRpt(TlsRpt),
+}
+
+/// a TXT record at a `_dmarc`, `_domainkey`, `_mta-sts` or `_smtp._tls` owner
+#[derive(Debug, PartialEq, Clone)]
+pub struct MailEntry {
+    /// absolute owner name
+    pub name: String,
+    pub policy: Result<MailPolicy, String>,
+}
+
+impl DnsRecord {
+    /// DMARC, DKIM, MTA-STS and TLS-RPT records of the zone, parsed and checked
+    pub fn mail_records(&self) -> Vec<MailEntry> {
+        let apex = self.zone_origin();
+        self.txt
+            .iter()
+            .flatten()
+            .filter_map(|txt| {
+                let owner = name::fqdn(&txt.name, &apex);
+                let lower = owner.to_ascii_lowercase();
+                let labels: Vec<&str> = lower.split('.').collect();
+                let parse: fn(&str) -> Result<MailPolicy, String> = match labels.as_slice() {
+                    ["_dmarc", ..] => |t| t.parse().map(MailPolicy::Dmarc),
+                    [_, "_domainkey", ..] => |t| t.parse().map(MailPolicy::Dkim),
+                    ["_mta-sts", ..] => |t| t.parse().map(MailPolicy::MtaSts),
+                    ["_smtp", "_tls", ..] => |t| t.parse().map(MailPolicy::TlsRpt),
+                    _ => return None,
+                };
+                let policy = joined_text(&txt.txt).and_then(|text| parse(&text));
+                Some(MailEntry { name: owner, policy })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,146 @@
+use std::cmp::Ordering;
+
+/// true when the name ends with an unescaped dot
+pub fn is_absolute(name: &str) -> bool {
+    if !name.ends_with('.') {
+        return false;
+    }
+    let backslashes = name[..name.len() - 1]
+        .bytes()
+        .rev()
+        .take_while(|b| *b == b'\\')
+        .count();
+    backslashes.is_multiple_of(2)
+}
+
+/// origin as an absolute name, missing origin is the root
+pub fn absolute_origin(origin: &str) -> String {
+    if origin.is_empty() || origin == "." {
+        ".".to_string()
+    } else if is_absolute(origin) {
+        origin.to_string()
+    } else {
+        format!("{origin}.")
+    }
+}
+
+/// fully qualified form of `name` in zone `origin`,
+/// `@` and empty names are the origin itself
+pub fn fqdn(name: &str, origin: &str) -> String {
+    let origin = absolute_origin(origin);
+    if name.is_empty() || name == "@" {
+        origin
+    } else if is_absolute(name) {
+        name.to_string()
+    } else if origin == "." {
+        format!("{name}.")
+    } else {
+        format!("{name}.{origin}")
+    }
+}
+
+/// `name` relative to `origin` when it is below it, the origin itself is `@`,
+/// other names are returned unchanged
+pub fn relative(name: &str, origin: &str) -> String {
+    let origin = absolute_origin(origin);
+    if !is_absolute(name) || origin == "." {
+        return name.to_string();
+    }
+    if name.eq_ignore_ascii_case(&origin) {
+        return "@".to_string();
+    }
+    let suffix = format!(".{origin}");
+    match name.len().checked_sub(suffix.len()) {
+        Some(cut) if cut > 0 && name.is_char_boundary(cut) && name[cut..].eq_ignore_ascii_case(&suffix) && is_absolute(&name[..=cut]) => {
+            name[..cut].to_string()
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// split an absolute name into unescaped labels, the root label is not included
+pub fn labels(name: &str) -> Result<Vec<Vec<u8>>, String> {
+    let mut ret = Vec::new();
+    if name == "." {
+        return Ok(ret);
+    }
+    let bytes = name.as_bytes();
+    let mut label = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => {
+                let digits = bytes.get(i + 1..i + 4).filter(|d| d.iter().all(u8::is_ascii_digit));
+                if let Some(digits) = digits {
+                    let value: u16 = std::str::from_utf8(digits).unwrap().parse().unwrap();
+                    if value > 255 {
+                        return Err(format!("invalid escape in name `{name}`"));
+                    }
+                    label.push(value as u8);
+                    i += 4;
+                } else if let Some(c) = bytes.get(i + 1) {
+                    label.push(*c);
+                    i += 2;
+                } else {
+                    return Err(format!("dangling escape in name `{name}`"));
+                }
+                continue;
+            }
+            b'.' => {
+                if label.is_empty() {
+                    return Err(format!("empty label in name `{name}`"));
+                }
+                ret.push(std::mem::take(&mut label));
+            }
+            c => label.push(c),
+        }
+        i += 1;
+    }
+    if !label.is_empty() {
+        ret.push(label);
+    }
+    if ret.iter().any(|l| l.len() > 63) {
+        return Err(format!("label longer than 63 octets in name `{name}`"));
+    }
+    Ok(ret)
+}
+
+/// uncompressed wire format of an absolute name,
+/// `lowercase` gives the canonical form of RFC 4034 section 6.2
+pub fn to_wire(name: &str, lowercase: bool) -> Result<Vec<u8>, String> {
+    let mut ret = Vec::new();
+    for label in labels(name)? {
+        ret.push(label.len() as u8);
+        if lowercase {
+            ret.extend(label.iter().map(u8::to_ascii_lowercase));
+        } else {
+            ret.extend(label);
+        }
+    }
+    ret.push(0);
+    if ret.len() > 255 {
+        return Err(format!("name `{name}` is longer than 255 octets"));
+    }
+    Ok(ret)
+}
+
+fn canonical_labels(name: &str) -> Vec<Vec<u8>> {
+    let mut ret = labels(name).unwrap_or_else(|_| {
+        name.trim_end_matches('.').split('.').map(|l| l.as_bytes().to_vec()).collect()
+    });
+    ret.iter_mut().for_each(|l| l.make_ascii_lowercase());
+    ret.reverse();
+    ret
+}
+
+/// canonical DNS name order of RFC 4034 section 6.1 for absolute names
+pub fn canonical_cmp(a: &str, b: &str) -> Ordering {
+    canonical_labels(a).cmp(&canonical_labels(b))
+}
+
+/// true when absolute `name` is `zone` or below it
+pub fn is_subdomain(name: &str, zone: &str) -> bool {
+    let name = canonical_labels(name);
+    let zone = canonical_labels(zone);
+    name.len() >= zone.len() && name[..zone.len()] == zone[..]
+}
@@ -1,4 +1,7 @@
 use crate::dns_structs::*;
+use crate::encoding::{base32hex_decode, base64_decode, dnssec_time_decode, hex_decode};
+use crate::name;
+use crate::record::joined_text;
 use regex::Regex;
 
 #[derive(Debug)]
@@ -35,6 +38,12 @@ impl GetName for Srv { fn get_name(&self) -> &str { &self.name } }
 impl GetName for Spf { fn get_name(&self) -> &str { &self.name } }
 impl GetName for Caa { fn get_name(&self) -> &str { &self.name } }
 impl GetName for Ds { fn get_name(&self) -> &str { &self.name } }
+impl GetName for Dnskey { fn get_name(&self) -> &str { &self.name } }
+impl GetName for Rrsig { fn get_name(&self) -> &str { &self.name } }
+impl GetName for Nsec { fn get_name(&self) -> &str { &self.name } }
+impl GetName for Nsec3 { fn get_name(&self) -> &str { &self.name } }
+impl GetName for Nsec3param { fn get_name(&self) -> &str { &self.name } }
+impl GetName for Zonemd { fn get_name(&self) -> &str { &self.name } }
 
 fn parse_soa(rr_tokens: &[&str]) -> Soa {
     let l = rr_tokens.len();
@@ -116,7 +125,7 @@ fn parse_ptr(rr_data: &NormalizedRR, records_so_far: &[Ptr], current_origin: &st
     let name = get_name(rr_data, records_so_far);
     let l = rr_data.tokens.len();
     Ptr {
-        fullname: format!("{}.{}", name, current_origin),
+        fullname: name::fqdn(&name, current_origin),
         name,
         host: rr_data.tokens[l - 1].to_string(),
         ttl: if rr_data.has_ttl { rr_data.tokens[1].parse().ok() } else { None },
@@ -153,20 +162,134 @@ fn parse_caa(rr_data: &NormalizedRR, records_so_far: &[Caa]) -> Caa {
         name,
         flags: rr_data.tokens[l - 3].parse().unwrap_or(0),
         tag: rr_data.tokens[l - 2].to_string(),
-        value: rr_data.tokens[l - 1].to_string(),
+        value: joined_text(&rr_data.tokens[l - 1]).unwrap_or_else(|_| rr_data.tokens[l - 1].to_string()),
         ttl: if rr_data.has_ttl { rr_data.tokens[1].parse().ok() } else { None },
     }
 }
 
-fn parse_ds(rr_data: &NormalizedRR, records_so_far: &[Ds]) -> Ds {
+fn parse_ds(rr_data: &NormalizedRR, records_so_far: &[Ds]) -> Result<Ds, String> {
     let name = get_name(rr_data, records_so_far);
-    let l = rr_data.tokens.len();
-    Ds {
+    let rdata = &rr_data.tokens[rr_data.type_index + 1..];
+    if rdata.len() < 4 {
+        return Err("DS record needs key tag, algorithm, digest type and digest".to_owned());
+    }
+    let ds = Ds {
+        name,
+        key_tag: rdata[0].parse().map_err(|_| format!("invalid DS key tag `{}`", rdata[0]))?,
+        algorithm: rdata[1].parse()?,
+        digest_type: rdata[2].parse()?,
+        digest: hex_decode(&rdata[3..].concat())?,
+        ttl: if rr_data.has_ttl { rr_data.tokens[1].parse().ok() } else { None },
+        raw: None,
+    };
+    ds.validate()?;
+    Ok(ds)
+}
+
+fn parse_dnskey(rr_data: &NormalizedRR, records_so_far: &[Dnskey]) -> Result<Dnskey, String> {
+    let name = get_name(rr_data, records_so_far);
+    let rdata = &rr_data.tokens[rr_data.type_index + 1..];
+    if rdata.len() < 4 {
+        return Err("DNSKEY record needs flags, protocol, algorithm and public key".to_owned());
+    }
+    Ok(Dnskey {
+        name,
+        flags: rdata[0].parse().map_err(|_| format!("invalid DNSKEY flags `{}`", rdata[0]))?,
+        protocol: rdata[1].parse().map_err(|_| format!("invalid DNSKEY protocol `{}`", rdata[1]))?,
+        algorithm: rdata[2].parse()?,
+        public_key: base64_decode(&rdata[3..].concat())?,
+        ttl: if rr_data.has_ttl { rr_data.tokens[1].parse().ok() } else { None },
+    })
+}
+
+fn parse_rrsig(rr_data: &NormalizedRR, records_so_far: &[Rrsig]) -> Result<Rrsig, String> {
+    let name = get_name(rr_data, records_so_far);
+    let rdata = &rr_data.tokens[rr_data.type_index + 1..];
+    if rdata.len() < 9 {
+        return Err("RRSIG record needs type covered, algorithm, labels, original TTL, expiration, inception, key tag, signer and signature".to_owned());
+    }
+    let number = |i: usize, field: &str| format!("invalid RRSIG {field} `{}`", rdata[i]);
+    Ok(Rrsig {
+        name,
+        type_covered: rdata[0].parse()?,
+        algorithm: rdata[1].parse()?,
+        labels: rdata[2].parse().map_err(|_| number(2, "labels"))?,
+        original_ttl: rdata[3].parse().map_err(|_| number(3, "original TTL"))?,
+        expiration: dnssec_time_decode(&rdata[4])?,
+        inception: dnssec_time_decode(&rdata[5])?,
+        key_tag: rdata[6].parse().map_err(|_| number(6, "key tag"))?,
+        signer_name: rdata[7].to_string(),
+        signature: base64_decode(&rdata[8..].concat())?,
+        ttl: if rr_data.has_ttl { rr_data.tokens[1].parse().ok() } else { None },
+    })
+}
+
+fn parse_types(tokens: &[String]) -> Result<Vec<RecordType>, String> {
+    tokens.iter().map(|t| t.parse()).collect()
+}
+
+fn parse_salt(salt: &str) -> Result<Vec<u8>, String> {
+    if salt == "-" { Ok(Vec::new()) } else { hex_decode(salt) }
+}
+
+fn parse_nsec(rr_data: &NormalizedRR, records_so_far: &[Nsec]) -> Result<Nsec, String> {
+    let name = get_name(rr_data, records_so_far);
+    let rdata = &rr_data.tokens[rr_data.type_index + 1..];
+    if rdata.is_empty() {
+        return Err("NSEC record needs the next domain name".to_owned());
+    }
+    Ok(Nsec {
+        name,
+        next_domain: rdata[0].to_string(),
+        types: parse_types(&rdata[1..])?,
+        ttl: if rr_data.has_ttl { rr_data.tokens[1].parse().ok() } else { None },
+    })
+}
+
+fn parse_nsec3(rr_data: &NormalizedRR, records_so_far: &[Nsec3]) -> Result<Nsec3, String> {
+    let name = get_name(rr_data, records_so_far);
+    let rdata = &rr_data.tokens[rr_data.type_index + 1..];
+    if rdata.len() < 5 {
+        return Err("NSEC3 record needs hash algorithm, flags, iterations, salt and next hashed owner".to_owned());
+    }
+    Ok(Nsec3 {
         name,
-        key_tag: rr_data.tokens[l-4].to_string(),
-        algorithm: rr_data.tokens[l-3].to_string(),
-        digest_type: rr_data.tokens[l-2].to_string(),
-        digest: rr_data.tokens[l - 1].to_string(),
+        hash_algorithm: rdata[0].parse().map_err(|_| format!("invalid NSEC3 hash algorithm `{}`", rdata[0]))?,
+        flags: rdata[1].parse().map_err(|_| format!("invalid NSEC3 flags `{}`", rdata[1]))?,
+        iterations: rdata[2].parse().map_err(|_| format!("invalid NSEC3 iterations `{}`", rdata[2]))?,
+        salt: parse_salt(&rdata[3])?,
+        next_hashed: base32hex_decode(&rdata[4])?,
+        types: parse_types(&rdata[5..])?,
+        ttl: if rr_data.has_ttl { rr_data.tokens[1].parse().ok() } else { None },
+    })
+}
+
+fn parse_nsec3param(rr_data: &NormalizedRR, records_so_far: &[Nsec3param]) -> Result<Nsec3param, String> {
+    let name = get_name(rr_data, records_so_far);
+    let rdata = &rr_data.tokens[rr_data.type_index + 1..];
+    if rdata.len() < 4 {
+        return Err("NSEC3PARAM record needs hash algorithm, flags, iterations and salt".to_owned());
+    }
+    Ok(Nsec3param {
+        name,
+        hash_algorithm: rdata[0].parse().map_err(|_| format!("invalid NSEC3PARAM hash algorithm `{}`", rdata[0]))?,
+        flags: rdata[1].parse().map_err(|_| format!("invalid NSEC3PARAM flags `{}`", rdata[1]))?,
+        iterations: rdata[2].parse().map_err(|_| format!("invalid NSEC3PARAM iterations `{}`", rdata[2]))?,
+        salt: parse_salt(&rdata[3])?,
+        ttl: if rr_data.has_ttl { rr_data.tokens[1].parse().ok() } else { None },
+    })
+}
+
+fn parse_zonemd(rr_data: &NormalizedRR, records_so_far: &[Zonemd]) -> Zonemd {
+    let name = get_name(rr_data, records_so_far);
+    let rdata = &rr_data.tokens[rr_data.type_index + 1..];
+    let field = |i: usize| rdata.get(i).map_or("", |s| s.as_str());
+    Zonemd {
+        name,
+        serial: field(0).parse().unwrap_or(0),
+        scheme: field(1).parse().unwrap_or(0),
+        hash_algorithm: field(2).parse().unwrap_or(0),
+        digest: hex_decode(&rdata.get(3..).unwrap_or_default().concat()).unwrap_or_default(),
         ttl: if rr_data.has_ttl { rr_data.tokens[1].parse().ok() } else { None },
     }
 }
@@ -185,6 +308,47 @@ fn flatten_soa(re: &Regex, re_whitespace: &Regex, text: &str) -> String {
     text.to_string()
 }
 
+/// join records spanning several lines in parentheses into one line
+fn flatten_parentheses(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for c in text.chars() {
+        if escaped {
+            escaped = false;
+            result.push(c);
+            continue;
+        }
+        match c {
+            '\\' => {
+                escaped = true;
+                result.push(c);
+            },
+            '"' => {
+                in_quotes = !in_quotes;
+                result.push(c);
+            },
+            '(' if !in_quotes => {
+                depth += 1;
+                result.push(' ');
+            },
+            ')' if !in_quotes && depth > 0 => {
+                depth -= 1;
+                result.push(' ');
+            },
+            '\n' => {
+                // quoted strings never span lines
+                in_quotes = false;
+                result.push(if depth > 0 { ' ' } else { c });
+            },
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
 fn split_args(input: &str, sep: Option<char>, keep_quotes: bool) -> Vec<String> {
     let mut result = Vec::new();
     let mut current_token = String::new();
@@ -296,7 +460,14 @@ fn normalize_rr(rr: &str) -> NormalizedRR {
     }
 }
 
-fn parse_rrs(text: &str) -> DnsRecord {
+fn push_or_warn<T>(records: &mut Option<Vec<T>>, parsed: Result<T, String>, rr: &str, warnings: &mut Vec<ParseWarning>) {
+    match parsed {
+        Ok(record) => records.get_or_insert_with(Vec::new).push(record),
+        Err(message) => warnings.push(ParseWarning { record: rr.to_string(), message }),
+    }
+}
+
+fn parse_rrs(text: &str, warnings: &mut Vec<ParseWarning>) -> DnsRecord {
     let mut zone = DnsRecord::default();
     let rrs = text.lines();
 
@@ -315,7 +486,12 @@ fn parse_rrs(text: &str) -> DnsRecord {
             let nrr_tokens: Vec<&str> = rr.split_whitespace().collect();
 
             match nrr.rr_type.as_str() {
-                "SOA" => zone.soa = Some(parse_soa(&nrr_tokens)),
+                "SOA" => {
+                    if let Some(previous) = zone.soa.replace(parse_soa(&nrr_tokens)) {
+                        let message = format!("more than one SOA record, replaces the one with serial {}", previous.serial);
+                        warnings.push(ParseWarning { record: rr.to_string(), message });
+                    }
+                },
                 "NS" => {
                     let records = zone.ns.get_or_insert_with(Vec::new);
                     records.push(parse_ns(&nrr, records));
@@ -341,9 +517,9 @@ fn parse_rrs(text: &str) -> DnsRecord {
                     records.push(parse_txt(&nrr, records));
                 },
                 "PTR" => {
+                    let origin = zone.zone_origin();
                     let records = zone.ptr.get_or_insert_with(Vec::new);
-                    let origin = zone.origin.as_deref().unwrap_or("");
-                    records.push(parse_ptr(&nrr, records, origin));
+                    records.push(parse_ptr(&nrr, records, &origin));
                 },
                 "SRV" => {
                     let records = zone.srv.get_or_insert_with(Vec::new);
@@ -359,7 +535,40 @@ fn parse_rrs(text: &str) -> DnsRecord {
                 },
                 "DS" => {
                     let records = zone.ds.get_or_insert_with(Vec::new);
-                    records.push(parse_ds(&nrr, records));
+                    match parse_ds(&nrr, records) {
+                        Ok(ds) => records.push(ds),
+                        Err(message) => {
+                            // legacy zones keep their malformed DS records
+                            let rdata = nrr.tokens[nrr.type_index + 1..].join(" ");
+                            let ttl = if nrr.has_ttl { nrr.tokens[1].parse().ok() } else { None };
+                            records.push(Ds::legacy(get_name(&nrr, records), rdata, ttl, message.clone()));
+                            warnings.push(ParseWarning { record: rr.to_string(), message });
+                        }
+                    }
+                },
+                "DNSKEY" => {
+                    let parsed = parse_dnskey(&nrr, zone.dnskey.as_deref().unwrap_or_default());
+                    push_or_warn(&mut zone.dnskey, parsed, rr, warnings);
+                },
+                "RRSIG" => {
+                    let parsed = parse_rrsig(&nrr, zone.rrsig.as_deref().unwrap_or_default());
+                    push_or_warn(&mut zone.rrsig, parsed, rr, warnings);
+                },
+                "NSEC" => {
+                    let parsed = parse_nsec(&nrr, zone.nsec.as_deref().unwrap_or_default());
+                    push_or_warn(&mut zone.nsec, parsed, rr, warnings);
+                },
+                "NSEC3" => {
+                    let parsed = parse_nsec3(&nrr, zone.nsec3.as_deref().unwrap_or_default());
+                    push_or_warn(&mut zone.nsec3, parsed, rr, warnings);
+                },
+                "NSEC3PARAM" => {
+                    let parsed = parse_nsec3param(&nrr, zone.nsec3param.as_deref().unwrap_or_default());
+                    push_or_warn(&mut zone.nsec3param, parsed, rr, warnings);
+                },
+                "ZONEMD" => {
+                    let records = zone.zonemd.get_or_insert_with(Vec::new);
+                    records.push(parse_zonemd(&nrr, records));
                 },
                 _ => { /* Unknown record type, do nothing */ }
             }
@@ -368,12 +577,16 @@ fn parse_rrs(text: &str) -> DnsRecord {
     zone
 }
 
-pub fn parse(re_ws: &Regex, re_soa: &Regex,text: &str) -> Result<DnsRecord, String> {
+/// parse the zone, records that can not be represented are skipped
+/// and reported in the returned warnings
+pub fn parse(re_ws: &Regex, re_soa: &Regex,text: &str) -> Result<(DnsRecord, Vec<ParseWarning>), String> {
     let without_comments = remove_comments(text);
     let flattened = flatten_soa(re_soa, re_ws, &without_comments);
-    let dns_zone = parse_rrs(&flattened);
+    let flattened = flatten_parentheses(&flattened);
+    let mut warnings = Vec::new();
+    let dns_zone = parse_rrs(&flattened, &mut warnings);
     if dns_zone.is_empty() {
         return Err("Invalid DNS Zonefile".to_owned());
     }
-    Ok(dns_zone)
+    Ok((dns_zone, warnings))
 }
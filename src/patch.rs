@@ -0,0 +1,139 @@
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::diff::{RecordChange, ZoneDiff};
+use crate::dns_structs::*;
+use crate::generator;
+use crate::name;
+use crate::record::Record;
+
+/// condition the zone has to meet before a patch is applied
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="serde", serde(tag = "check", rename_all = "kebab-case"))]
+pub enum Precondition {
+    /// a record with the owner, type and data is in the zone
+    Exists { record: Record },
+    /// no record with the owner, type and data is in the zone
+    Absent { record: Record },
+}
+
+/// one change of a `ZonePatch`, names relative to the zone origin may be
+/// used and records are matched on owner, type and data, not ttl
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="serde", serde(tag = "op", rename_all = "kebab-case"))]
+pub enum PatchOperation {
+    /// add a record that is not in the zone yet
+    Add { record: Record },
+    /// delete a record that is in the zone
+    Delete { record: Record },
+    /// delete the records of a type at a name
+    DeleteRrset { name: String, rtype: RecordType },
+    /// delete every record at a name, except the SOA
+    DeleteName { name: String },
+    /// put `records` in place of the records of a type at a name
+    ReplaceRrset { name: String, rtype: RecordType, records: Vec<Record> },
+}
+
+/// changes to apply to a zone with `DnsRecord::apply`, all or none of them
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct ZonePatch {
+    #[cfg_attr(feature="serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub preconditions: Vec<Precondition>,
+    pub operations: Vec<PatchOperation>,
+}
+
+impl From<&ZoneDiff> for ZonePatch {
+    /// patch turning the old zone of the diff into the new one, every
+    /// record the diff removes or changes has to be there unchanged
+    fn from(diff: &ZoneDiff) -> Self {
+        let mut ret = ZonePatch::default();
+        for change in diff.changes() {
+            match change {
+                RecordChange::Added { record } => ret.operations.push(PatchOperation::Add { record: record.clone() }),
+                RecordChange::Removed { record } => ret.operations.push(PatchOperation::Delete { record: record.clone() }),
+                RecordChange::Modified { old, new } | RecordChange::Ttl { old, new } => {
+                    ret.operations.push(PatchOperation::Delete { record: old.clone() });
+                    ret.operations.push(PatchOperation::Add { record: new.clone() });
+                }
+            }
+        }
+        ret
+    }
+}
+
+/// owner, type and data of two records are the same, names resolved against `origin`
+fn same_record(a: &Record, b: &Record, origin: &str) -> bool {
+    a.record_type() == b.record_type()
+        && same_name(a.name(), b.name(), origin)
+        && match (a.rdata_wire(origin), b.rdata_wire(origin)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => {
+                let (mut a, mut b) = (a.clone(), b.clone());
+                a.set_ttl(None);
+                b.set_ttl(None);
+                a == b
+            }
+        }
+}
+
+fn same_name(a: &str, b: &str, origin: &str) -> bool {
+    name::canonical_cmp(&name::fqdn(a, origin), &name::fqdn(b, origin)).is_eq()
+}
+
+impl DnsRecord {
+    /// apply the operations of `patch` in order, when every precondition holds
+    ///
+    /// adding a record already in the zone or deleting one that is not fails
+    /// the patch, so a change based on an outdated copy of the zone does not
+    /// overwrite someone else's. on error the zone is left as it was
+    pub fn apply(&mut self, patch: &ZonePatch) -> Result<(), String> {
+        let origin = self.zone_origin();
+        let mut zone = self.clone();
+        let contains = |zone: &DnsRecord, record: &Record| zone.records().iter().any(|r| same_record(r, record, &origin));
+        for precondition in &patch.preconditions {
+            match precondition {
+                Precondition::Exists { record } if !contains(&zone, record) => {
+                    return Err(format!("precondition failed, not in the zone: {}", generator::record_line(record)));
+                }
+                Precondition::Absent { record } if contains(&zone, record) => {
+                    return Err(format!("precondition failed, already in the zone: {}", generator::record_line(record)));
+                }
+                _ => {}
+            }
+        }
+        for operation in &patch.operations {
+            match operation {
+                PatchOperation::Add { record } => {
+                    if contains(&zone, record) {
+                        return Err(format!("can not add, already in the zone: {}", generator::record_line(record)));
+                    }
+                    zone.push(record.clone());
+                }
+                PatchOperation::Delete { record } => {
+                    if !contains(&zone, record) {
+                        return Err(format!("can not delete, not in the zone: {}", generator::record_line(record)));
+                    }
+                    zone.retain(|r| !same_record(r, record, &origin));
+                }
+                PatchOperation::DeleteRrset { name, rtype } => {
+                    zone.retain(|r| r.record_type() != *rtype || !same_name(r.name(), name, &origin));
+                }
+                PatchOperation::DeleteName { name } => {
+                    zone.retain(|r| matches!(r, Record::Soa(_)) || !same_name(r.name(), name, &origin));
+                }
+                PatchOperation::ReplaceRrset { name, rtype, records } => {
+                    if let Some(r) = records.iter().find(|r| r.record_type() != *rtype || !same_name(r.name(), name, &origin)) {
+                        return Err(format!("{} is not a {rtype} record at {name}", generator::record_line(r)));
+                    }
+                    zone.retain(|r| r.record_type() != *rtype || !same_name(r.name(), name, &origin));
+                    records.iter().cloned().for_each(|r| zone.push(r));
+                }
+            }
+        }
+        *self = zone;
+        Ok(())
+    }
+}
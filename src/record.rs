@@ -0,0 +1,656 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dns_structs::*;
+use crate::name;
+
+/// a single resource record of any type DnsRecord can hold
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "lowercase"))]
+pub enum Record {
+    Soa(Soa),
+    Ns(Ns),
+    A(A),
+    Aaaa(Aaaa),
+    Cname(Cname),
+    Mx(Mx),
+    Txt(Txt),
+    Ptr(Ptr),
+    Srv(Srv),
+    Spf(Spf),
+    Caa(Caa),
+    Ds(Ds),
+    Dnskey(Dnskey),
+    Rrsig(Rrsig),
+    Nsec(Nsec),
+    Nsec3(Nsec3),
+    Nsec3param(Nsec3param),
+    Zonemd(Zonemd),
+}
+
+impl Record {
+    pub fn record_type(&self) -> RecordType {
+        match self {
+            Record::Soa(_) => RecordType::Soa,
+            Record::Ns(_) => RecordType::Ns,
+            Record::A(_) => RecordType::A,
+            Record::Aaaa(_) => RecordType::Aaaa,
+            Record::Cname(_) => RecordType::Cname,
+            Record::Mx(_) => RecordType::Mx,
+            Record::Txt(_) => RecordType::Txt,
+            Record::Ptr(_) => RecordType::Ptr,
+            Record::Srv(_) => RecordType::Srv,
+            Record::Spf(_) => RecordType::Spf,
+            Record::Caa(_) => RecordType::Caa,
+            Record::Ds(_) => RecordType::Ds,
+            Record::Dnskey(_) => RecordType::Dnskey,
+            Record::Rrsig(_) => RecordType::Rrsig,
+            Record::Nsec(_) => RecordType::Nsec,
+            Record::Nsec3(_) => RecordType::Nsec3,
+            Record::Nsec3param(_) => RecordType::Nsec3param,
+            Record::Zonemd(_) => RecordType::Zonemd,
+        }
+    }
+
+    /// owner name as written in the zone, may be relative or `@`
+    pub fn name(&self) -> &str {
+        match self {
+            Record::Soa(r) => &r.name,
+            Record::Ns(r) => &r.name,
+            Record::A(r) => &r.name,
+            Record::Aaaa(r) => &r.name,
+            Record::Cname(r) => &r.name,
+            Record::Mx(r) => &r.name,
+            Record::Txt(r) => &r.name,
+            Record::Ptr(r) => &r.name,
+            Record::Srv(r) => &r.name,
+            Record::Spf(r) => &r.name,
+            Record::Caa(r) => &r.name,
+            Record::Ds(r) => &r.name,
+            Record::Dnskey(r) => &r.name,
+            Record::Rrsig(r) => &r.name,
+            Record::Nsec(r) => &r.name,
+            Record::Nsec3(r) => &r.name,
+            Record::Nsec3param(r) => &r.name,
+            Record::Zonemd(r) => &r.name,
+        }
+    }
+
+    pub fn ttl(&self) -> Option<u32> {
+        match self {
+            Record::Soa(r) => r.ttl,
+            Record::Ns(r) => r.ttl,
+            Record::A(r) => r.ttl,
+            Record::Aaaa(r) => r.ttl,
+            Record::Cname(r) => r.ttl,
+            Record::Mx(r) => r.ttl,
+            Record::Txt(r) => r.ttl,
+            Record::Ptr(r) => r.ttl,
+            Record::Srv(r) => r.ttl,
+            Record::Spf(r) => r.ttl,
+            Record::Caa(r) => r.ttl,
+            Record::Ds(r) => r.ttl,
+            Record::Dnskey(r) => r.ttl,
+            Record::Rrsig(r) => r.ttl,
+            Record::Nsec(r) => r.ttl,
+            Record::Nsec3(r) => r.ttl,
+            Record::Nsec3param(r) => r.ttl,
+            Record::Zonemd(r) => r.ttl,
+        }
+    }
+
+    pub(crate) fn set_ttl(&mut self, ttl: Option<u32>) {
+        match self {
+            Record::Soa(r) => r.ttl = ttl,
+            Record::Ns(r) => r.ttl = ttl,
+            Record::A(r) => r.ttl = ttl,
+            Record::Aaaa(r) => r.ttl = ttl,
+            Record::Cname(r) => r.ttl = ttl,
+            Record::Mx(r) => r.ttl = ttl,
+            Record::Txt(r) => r.ttl = ttl,
+            Record::Ptr(r) => r.ttl = ttl,
+            Record::Srv(r) => r.ttl = ttl,
+            Record::Spf(r) => r.ttl = ttl,
+            Record::Caa(r) => r.ttl = ttl,
+            Record::Ds(r) => r.ttl = ttl,
+            Record::Dnskey(r) => r.ttl = ttl,
+            Record::Rrsig(r) => r.ttl = ttl,
+            Record::Nsec(r) => r.ttl = ttl,
+            Record::Nsec3(r) => r.ttl = ttl,
+            Record::Nsec3param(r) => r.ttl = ttl,
+            Record::Zonemd(r) => r.ttl = ttl,
+        }
+    }
+
+    /// RDATA in uncompressed wire format, names are made absolute against `origin`
+    /// and lower-cased for the types listed in RFC 4034 section 6.2
+    pub(crate) fn rdata_wire(&self, origin: &str) -> Result<Vec<u8>, String> {
+        let wire_name = |n: &str| name::to_wire(&name::fqdn(n, origin), true);
+        let mut ret = Vec::new();
+        match self {
+            Record::Soa(r) => {
+                ret.extend(wire_name(&r.mname)?);
+                ret.extend(wire_name(&r.rname)?);
+                for v in [r.serial.number()?, r.refresh, r.retry, r.expire, r.minimum] {
+                    ret.extend(v.to_be_bytes());
+                }
+            }
+            Record::Ns(r) => ret.extend(wire_name(&r.host)?),
+            Record::Cname(r) => ret.extend(wire_name(&r.alias)?),
+            Record::Ptr(r) => ret.extend(wire_name(&r.host)?),
+            Record::A(r) => {
+                let ip: Ipv4Addr = r.ip.parse().map_err(|_| format!("invalid IPv4 address `{}`", r.ip))?;
+                ret.extend(ip.octets());
+            }
+            Record::Aaaa(r) => {
+                let ip: Ipv6Addr = r.ip.parse().map_err(|_| format!("invalid IPv6 address `{}`", r.ip))?;
+                ret.extend(ip.octets());
+            }
+            Record::Mx(r) => {
+                ret.extend(r.preference.to_be_bytes());
+                ret.extend(wire_name(&r.host)?);
+            }
+            Record::Txt(r) => ret.extend(character_strings_wire(&r.txt)?),
+            Record::Spf(r) => ret.extend(character_strings_wire(&r.data)?),
+            Record::Srv(r) => {
+                ret.extend(r.priority.to_be_bytes());
+                ret.extend(r.weight.to_be_bytes());
+                ret.extend(r.port.to_be_bytes());
+                ret.extend(wire_name(&r.target)?);
+            }
+            Record::Caa(r) => {
+                if r.tag.is_empty() || r.tag.len() > 255 {
+                    return Err(format!("invalid CAA tag `{}`", r.tag));
+                }
+                ret.push(r.flags);
+                ret.push(r.tag.len() as u8);
+                ret.extend(r.tag.as_bytes());
+                ret.extend(r.value.as_bytes());
+            }
+            Record::Ds(r) => {
+                if let Some(raw) = &r.raw {
+                    return Err(format!("malformed DS record `{}`: {}", r.name, raw.error));
+                }
+                ret.extend(r.key_tag.to_be_bytes());
+                ret.push(r.algorithm.into());
+                ret.push(r.digest_type.into());
+                ret.extend(&r.digest);
+            }
+            Record::Dnskey(r) => {
+                ret.extend(r.flags.to_be_bytes());
+                ret.push(r.protocol);
+                ret.push(r.algorithm.into());
+                ret.extend(&r.public_key);
+            }
+            Record::Rrsig(r) => {
+                ret.extend(r.type_covered.code().to_be_bytes());
+                ret.push(r.algorithm.into());
+                ret.push(r.labels);
+                ret.extend(r.original_ttl.to_be_bytes());
+                ret.extend(r.expiration.to_be_bytes());
+                ret.extend(r.inception.to_be_bytes());
+                ret.extend(r.key_tag.to_be_bytes());
+                ret.extend(wire_name(&r.signer_name)?);
+                ret.extend(&r.signature);
+            }
+            Record::Nsec(r) => {
+                // the next domain name keeps its case (RFC 6840 section 5.1)
+                ret.extend(name::to_wire(&name::fqdn(&r.next_domain, origin), false)?);
+                ret.extend(type_bitmap_wire(&r.types));
+            }
+            Record::Nsec3(r) => {
+                if r.salt.len() > 255 || r.next_hashed.len() > 255 {
+                    return Err("NSEC3 salt or hash longer than 255 octets".to_owned());
+                }
+                ret.push(r.hash_algorithm);
+                ret.push(r.flags);
+                ret.extend(r.iterations.to_be_bytes());
+                ret.push(r.salt.len() as u8);
+                ret.extend(&r.salt);
+                ret.push(r.next_hashed.len() as u8);
+                ret.extend(&r.next_hashed);
+                ret.extend(type_bitmap_wire(&r.types));
+            }
+            Record::Nsec3param(r) => {
+                if r.salt.len() > 255 {
+                    return Err("NSEC3PARAM salt longer than 255 octets".to_owned());
+                }
+                ret.push(r.hash_algorithm);
+                ret.push(r.flags);
+                ret.extend(r.iterations.to_be_bytes());
+                ret.push(r.salt.len() as u8);
+                ret.extend(&r.salt);
+            }
+            Record::Zonemd(r) => {
+                ret.extend(r.serial.to_be_bytes());
+                ret.push(r.scheme);
+                ret.push(r.hash_algorithm);
+                ret.extend(&r.digest);
+            }
+        }
+        if ret.len() > u16::MAX as usize {
+            return Err(format!("RDATA of {} record `{}` is too long", self.record_type(), self.name()));
+        }
+        Ok(ret)
+    }
+}
+
+/// split presentation format TXT data into its character-strings,
+/// quoted or bare, with `\X` and `\DDD` escapes resolved
+pub(crate) fn parse_character_strings(data: &str) -> Result<Vec<Vec<u8>>, String> {
+    Ok(character_string_tokens(data)?.into_iter().map(|(_, s)| s).collect())
+}
+
+/// character-strings of presentation format TXT data as written and resolved
+fn character_string_tokens(data: &str) -> Result<Vec<(&str, Vec<u8>)>, String> {
+    let bytes = data.as_bytes();
+    let mut ret = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let quoted = bytes[i] == b'"';
+        if quoted {
+            i += 1;
+        }
+        let mut current = Vec::new();
+        loop {
+            let Some(&c) = bytes.get(i) else {
+                if quoted {
+                    return Err(format!("unterminated quoted string in `{data}`"));
+                }
+                break;
+            };
+            if quoted && c == b'"' {
+                i += 1;
+                break;
+            }
+            if !quoted && c.is_ascii_whitespace() {
+                break;
+            }
+            if c == b'\\' {
+                let digits = bytes.get(i + 1..i + 4).filter(|d| d.iter().all(u8::is_ascii_digit));
+                if let Some(digits) = digits {
+                    let value: u16 = std::str::from_utf8(digits).unwrap().parse().unwrap();
+                    if value > 255 {
+                        return Err(format!("invalid escape in `{data}`"));
+                    }
+                    current.push(value as u8);
+                    i += 4;
+                } else if let Some(&next) = bytes.get(i + 1) {
+                    current.push(next);
+                    i += 2;
+                } else {
+                    return Err(format!("dangling escape in `{data}`"));
+                }
+                continue;
+            }
+            current.push(c);
+            i += 1;
+        }
+        ret.push((&data[start..i], current));
+    }
+    Ok(ret)
+}
+
+/// TXT or SPF data as quoted character-strings of at most 255 octets
+///
+/// data starting with a quote is presentation format, its strings are kept
+/// as written unless they are too long. anything else is a single text
+/// which is quoted with `"` and `\` escaped
+pub(crate) fn character_strings(data: &str) -> Result<Vec<String>, String> {
+    if !data.trim_start().starts_with('"') {
+        return Ok(split_character_string(data.as_bytes()));
+    }
+    let mut ret = Vec::new();
+    for (raw, value) in character_string_tokens(data)? {
+        if value.len() > 255 {
+            ret.extend(split_character_string(&value));
+        } else {
+            ret.push(raw.to_string());
+        }
+    }
+    Ok(ret)
+}
+
+/// quoted chunks of at most 255 octets, multi-byte characters are not split
+fn split_character_string(value: &[u8]) -> Vec<String> {
+    if value.is_empty() {
+        return vec!["\"\"".to_string()];
+    }
+    let mut ret = Vec::new();
+    let mut start = 0;
+    while start < value.len() {
+        let mut end = (start + 255).min(value.len());
+        while end < value.len() && end > start + 1 && value[end] & 0xc0 == 0x80 {
+            end -= 1;
+        }
+        ret.push(quote_character_string(&value[start..end]));
+        start = end;
+    }
+    ret
+}
+
+pub(crate) fn quote_character_string(value: &[u8]) -> String {
+    let mut ret = String::from("\"");
+    match std::str::from_utf8(value) {
+        Ok(text) => {
+            for c in text.chars() {
+                match c {
+                    '"' | '\\' => {
+                        ret.push('\\');
+                        ret.push(c);
+                    }
+                    c if c.is_ascii_control() => ret.push_str(&format!("\\{:03}", c as u8)),
+                    c => ret.push(c),
+                }
+            }
+        }
+        Err(_) => {
+            for &b in value {
+                match b {
+                    b'"' | b'\\' => {
+                        ret.push('\\');
+                        ret.push(b as char);
+                    }
+                    0x20..=0x7e => ret.push(b as char),
+                    b => ret.push_str(&format!("\\{b:03}")),
+                }
+            }
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+impl Txt {
+    /// the TXT data as quoted character-strings of at most 255 octets each,
+    /// ready to be written to a zone file
+    ///
+    /// `txt` starting with a quote is taken as presentation format and only
+    /// strings longer than 255 octets are split, any other value is one text
+    /// that is quoted and escaped, like a DKIM key stored as a single string
+    pub fn to_character_strings(&self) -> Result<Vec<String>, String> {
+        character_strings(&self.txt)
+    }
+
+    /// the character-strings joined without a separator, the way SPF,
+    /// DKIM and DMARC read a TXT record (RFC 7208 section 3.3)
+    pub fn text(&self) -> Result<String, String> {
+        joined_text(&self.txt)
+    }
+}
+
+/// TXT or SPF data as one text, presentation format is unquoted and joined
+pub(crate) fn joined_text(data: &str) -> Result<String, String> {
+    if !data.trim_start().starts_with('"') {
+        return Ok(data.to_string());
+    }
+    let bytes: Vec<u8> = parse_character_strings(data)?.concat();
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn character_strings_wire(data: &str) -> Result<Vec<u8>, String> {
+    let mut ret = Vec::new();
+    for s in parse_character_strings(data)? {
+        if s.len() > 255 {
+            return Err(format!("character-string longer than 255 octets in `{data}`"));
+        }
+        ret.push(s.len() as u8);
+        ret.extend(s);
+    }
+    Ok(ret)
+}
+
+/// type bitmap of NSEC and NSEC3 records (RFC 4034 section 4.1.2)
+pub(crate) fn type_bitmap_wire(types: &[RecordType]) -> Vec<u8> {
+    let mut codes: Vec<u16> = types.iter().map(RecordType::code).collect();
+    codes.sort_unstable();
+    codes.dedup();
+    let mut ret = Vec::new();
+    for window in 0..=255u8 {
+        let in_window: Vec<u8> = codes
+            .iter()
+            .filter(|c| (*c >> 8) as u8 == window)
+            .map(|c| (*c & 0xff) as u8)
+            .collect();
+        let Some(last) = in_window.last() else {
+            continue;
+        };
+        let mut bitmap = vec![0u8; *last as usize / 8 + 1];
+        for low in in_window {
+            bitmap[low as usize / 8] |= 0x80 >> (low % 8);
+        }
+        ret.push(window);
+        ret.push(bitmap.len() as u8);
+        ret.extend(bitmap);
+    }
+    ret
+}
+
+/// resource record in canonical form (RFC 4034 section 6.2)
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct WireRecord {
+    /// absolute owner name as written in the zone
+    pub name: String,
+    pub rtype: u16,
+    pub ttl: u32,
+    pub rdata: Vec<u8>,
+}
+
+impl WireRecord {
+    /// owner | type | class | ttl | rdlength | rdata
+    pub fn to_wire(&self) -> Result<Vec<u8>, String> {
+        let mut ret = name::to_wire(&self.name, true)?;
+        ret.extend(self.rtype.to_be_bytes());
+        ret.extend(1u16.to_be_bytes());
+        ret.extend(self.ttl.to_be_bytes());
+        ret.extend((self.rdata.len() as u16).to_be_bytes());
+        ret.extend(&self.rdata);
+        Ok(ret)
+    }
+}
+
+impl DnsRecord {
+    /// every record of the zone as one list, SOA first, then each record set in turn
+    pub fn records(&self) -> Vec<Record> {
+        fn push_all<T: Clone>(ret: &mut Vec<Record>, data: &Option<Vec<T>>, f: fn(T) -> Record) {
+            ret.extend(data.iter().flatten().cloned().map(f));
+        }
+        let mut ret = Vec::new();
+        if let Some(soa) = &self.soa {
+            ret.push(Record::Soa(soa.clone()));
+        }
+        push_all(&mut ret, &self.ns, Record::Ns);
+        push_all(&mut ret, &self.mx, Record::Mx);
+        push_all(&mut ret, &self.a, Record::A);
+        push_all(&mut ret, &self.aaaa, Record::Aaaa);
+        push_all(&mut ret, &self.cname, Record::Cname);
+        push_all(&mut ret, &self.ptr, Record::Ptr);
+        push_all(&mut ret, &self.txt, Record::Txt);
+        push_all(&mut ret, &self.srv, Record::Srv);
+        push_all(&mut ret, &self.spf, Record::Spf);
+        push_all(&mut ret, &self.caa, Record::Caa);
+        push_all(&mut ret, &self.ds, Record::Ds);
+        push_all(&mut ret, &self.dnskey, Record::Dnskey);
+        push_all(&mut ret, &self.nsec3param, Record::Nsec3param);
+        push_all(&mut ret, &self.nsec, Record::Nsec);
+        push_all(&mut ret, &self.nsec3, Record::Nsec3);
+        push_all(&mut ret, &self.rrsig, Record::Rrsig);
+        push_all(&mut ret, &self.zonemd, Record::Zonemd);
+        ret
+    }
+
+    /// add `record` to the record set of its type, a SOA replaces the zone's
+    pub fn push(&mut self, record: Record) {
+        match record {
+            Record::Soa(r) => self.soa = Some(r),
+            Record::Ns(r) => self.ns.get_or_insert_with(Vec::new).push(r),
+            Record::A(r) => self.a.get_or_insert_with(Vec::new).push(r),
+            Record::Aaaa(r) => self.aaaa.get_or_insert_with(Vec::new).push(r),
+            Record::Cname(r) => self.cname.get_or_insert_with(Vec::new).push(r),
+            Record::Mx(r) => self.mx.get_or_insert_with(Vec::new).push(r),
+            Record::Txt(r) => self.txt.get_or_insert_with(Vec::new).push(r),
+            Record::Ptr(r) => self.ptr.get_or_insert_with(Vec::new).push(r),
+            Record::Srv(r) => self.srv.get_or_insert_with(Vec::new).push(r),
+            Record::Spf(r) => self.spf.get_or_insert_with(Vec::new).push(r),
+            Record::Caa(r) => self.caa.get_or_insert_with(Vec::new).push(r),
+            Record::Ds(r) => self.ds.get_or_insert_with(Vec::new).push(r),
+            Record::Dnskey(r) => self.dnskey.get_or_insert_with(Vec::new).push(r),
+            Record::Rrsig(r) => self.rrsig.get_or_insert_with(Vec::new).push(r),
+            Record::Nsec(r) => self.nsec.get_or_insert_with(Vec::new).push(r),
+            Record::Nsec3(r) => self.nsec3.get_or_insert_with(Vec::new).push(r),
+            Record::Nsec3param(r) => self.nsec3param.get_or_insert_with(Vec::new).push(r),
+            Record::Zonemd(r) => self.zonemd.get_or_insert_with(Vec::new).push(r),
+        }
+    }
+
+    /// keep the records `keep` is true for, in their order
+    pub(crate) fn retain(&mut self, keep: impl Fn(&Record) -> bool) {
+        let records = self.records();
+        *self = DnsRecord { origin: self.origin.take(), ttl: self.ttl, ..Default::default() };
+        records.into_iter().filter(|r| keep(r)).for_each(|r| self.push(r));
+    }
+
+    /// make the zone independent of the order records were added in
+    ///
+    /// owner names and the names in RDATA (RFC 4034 section 6.2, without the
+    /// NSEC next domain name as RFC 6840 section 5.1 asks) are lower-cased, every record set is sorted in canonical order so the
+    /// records of an RRset follow each other, and exact duplicates are removed
+    pub fn canonicalize(&mut self) -> Result<(), String> {
+        fn each<T>(data: &mut Option<Vec<T>>, f: impl Fn(&mut T)) {
+            data.iter_mut().flatten().for_each(f);
+        }
+        if let Some(soa) = self.soa.as_mut() {
+            soa.name.make_ascii_lowercase();
+            soa.mname.make_ascii_lowercase();
+            soa.rname.make_ascii_lowercase();
+        }
+        each(&mut self.ns, |r| {
+            r.name.make_ascii_lowercase();
+            r.host.make_ascii_lowercase();
+        });
+        each(&mut self.cname, |r| {
+            r.name.make_ascii_lowercase();
+            r.alias.make_ascii_lowercase();
+        });
+        each(&mut self.mx, |r| {
+            r.name.make_ascii_lowercase();
+            r.host.make_ascii_lowercase();
+        });
+        each(&mut self.ptr, |r| {
+            r.name.make_ascii_lowercase();
+            r.host.make_ascii_lowercase();
+        });
+        each(&mut self.srv, |r| {
+            r.name.make_ascii_lowercase();
+            r.target.make_ascii_lowercase();
+        });
+        each(&mut self.rrsig, |r| {
+            r.name.make_ascii_lowercase();
+            r.signer_name.make_ascii_lowercase();
+        });
+        // the next domain name of NSEC keeps its case (RFC 6840 section 5.1)
+        each(&mut self.nsec, |r| r.name.make_ascii_lowercase());
+        each(&mut self.a, |r| r.name.make_ascii_lowercase());
+        each(&mut self.aaaa, |r| r.name.make_ascii_lowercase());
+        each(&mut self.txt, |r| r.name.make_ascii_lowercase());
+        each(&mut self.spf, |r| r.name.make_ascii_lowercase());
+        each(&mut self.caa, |r| r.name.make_ascii_lowercase());
+        each(&mut self.ds, |r| r.name.make_ascii_lowercase());
+        each(&mut self.dnskey, |r| r.name.make_ascii_lowercase());
+        each(&mut self.nsec3param, |r| r.name.make_ascii_lowercase());
+        each(&mut self.nsec3, |r| r.name.make_ascii_lowercase());
+        each(&mut self.zonemd, |r| r.name.make_ascii_lowercase());
+
+        let origin = self.zone_origin();
+        sort_set(&mut self.ns, &origin, Record::Ns)?;
+        sort_set(&mut self.mx, &origin, Record::Mx)?;
+        sort_set(&mut self.a, &origin, Record::A)?;
+        sort_set(&mut self.aaaa, &origin, Record::Aaaa)?;
+        sort_set(&mut self.cname, &origin, Record::Cname)?;
+        sort_set(&mut self.ptr, &origin, Record::Ptr)?;
+        sort_set(&mut self.txt, &origin, Record::Txt)?;
+        sort_set(&mut self.srv, &origin, Record::Srv)?;
+        sort_set(&mut self.spf, &origin, Record::Spf)?;
+        sort_set(&mut self.caa, &origin, Record::Caa)?;
+        sort_set(&mut self.ds, &origin, Record::Ds)?;
+        sort_set(&mut self.dnskey, &origin, Record::Dnskey)?;
+        sort_set(&mut self.nsec3param, &origin, Record::Nsec3param)?;
+        sort_set(&mut self.nsec, &origin, Record::Nsec)?;
+        sort_set(&mut self.nsec3, &origin, Record::Nsec3)?;
+        sort_set(&mut self.rrsig, &origin, Record::Rrsig)?;
+        sort_set(&mut self.zonemd, &origin, Record::Zonemd)?;
+        Ok(())
+    }
+
+    /// absolute zone origin, `$ORIGIN` or an absolute SOA owner, else the root
+    pub(crate) fn zone_origin(&self) -> String {
+        match (&self.origin, &self.soa) {
+            (Some(origin), _) => name::absolute_origin(origin),
+            (None, Some(soa)) if name::is_absolute(&soa.name) => soa.name.clone(),
+            _ => ".".to_string(),
+        }
+    }
+
+    /// ttl a name server gives a record, falling back to `$TTL` then the SOA
+    pub(crate) fn effective_ttl(&self, ttl: Option<u32>) -> u32 {
+        ttl.or(self.ttl)
+            .or_else(|| self.soa.as_ref().and_then(|s| s.ttl))
+            .or_else(|| self.soa.as_ref().map(|s| s.minimum))
+            .unwrap_or(0)
+    }
+
+    /// all in-zone records in canonical form, sorted in canonical order
+    /// (owner name, type, RDATA) with duplicates removed
+    pub(crate) fn canonical_records(&self) -> Result<Vec<WireRecord>, String> {
+        let origin = self.zone_origin();
+        let mut ret = Vec::new();
+        for record in self.records() {
+            let owner = name::fqdn(record.name(), &origin);
+            if !name::is_subdomain(&owner, &origin) {
+                continue;
+            }
+            ret.push(WireRecord {
+                rdata: record.rdata_wire(&origin)?,
+                rtype: record.record_type().code(),
+                ttl: self.effective_ttl(record.ttl()),
+                name: owner,
+            });
+        }
+        ret.sort_by(|a, b| {
+            name::canonical_cmp(&a.name, &b.name)
+                .then(a.rtype.cmp(&b.rtype))
+                .then(a.rdata.cmp(&b.rdata))
+        });
+        ret.dedup_by(|a, b| {
+            a.rtype == b.rtype && a.rdata == b.rdata && name::canonical_cmp(&a.name, &b.name).is_eq()
+        });
+        Ok(ret)
+    }
+}
+
+/// sort one record set by owner name then RDATA, dropping records with the
+/// same owner, RDATA and ttl as the one before
+fn sort_set<T: Clone>(data: &mut Option<Vec<T>>, origin: &str, f: fn(T) -> Record) -> Result<(), String> {
+    let Some(records) = data else {
+        return Ok(());
+    };
+    let mut keyed = records
+        .iter()
+        .map(|r| {
+            let record = f(r.clone());
+            Ok((name::fqdn(record.name(), origin), record.rdata_wire(origin)?, record.ttl(), r.clone()))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    keyed.sort_by(|a, b| name::canonical_cmp(&a.0, &b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    keyed.dedup_by(|a, b| a.1 == b.1 && a.2 == b.2 && name::canonical_cmp(&a.0, &b.0).is_eq());
+    *records = keyed.into_iter().map(|(_, _, _, r)| r).collect();
+    Ok(())
+}
@@ -0,0 +1,257 @@
+use std::fmt::Display;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use crate::dns_structs::*;
+use crate::name;
+
+/// network a reverse zone is generated for
+///
+/// IPv4 networks of any prefix up to /31, /25 to /31 are delegated the
+/// classless way of RFC 2317 and the others are split into the /8, /16 or /24
+/// zones that cover them, IPv6 networks end on a nibble boundary
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ReverseNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl ReverseNetwork {
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Self, String> {
+        let valid = match addr {
+            IpAddr::V4(_) => prefix > 0 && prefix < 32,
+            IpAddr::V6(_) => prefix > 0 && prefix < 128 && prefix.is_multiple_of(4),
+        };
+        if !valid {
+            return Err(format!("no reverse zone for `{addr}/{prefix}`, IPv4 needs /1 to /31 and IPv6 a multiple of 4"));
+        }
+        let network = Self { addr, prefix };
+        if network.bits(addr) != bits(addr) {
+            return Err(format!("`{addr}/{prefix}` has host bits set"));
+        }
+        Ok(network)
+    }
+
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// true when `ip` is inside the network
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        ip.is_ipv4() == self.addr.is_ipv4() && self.bits(ip) == bits(self.addr)
+    }
+
+    /// networks of the reverse zones that cover this one, like sixteen /24
+    /// for a /20, the network itself when it has a zone of its own
+    pub fn zones(&self) -> Vec<ReverseNetwork> {
+        let IpAddr::V4(addr) = self.addr else {
+            return vec![*self];
+        };
+        if self.prefix.is_multiple_of(8) || self.prefix > 24 {
+            return vec![*self];
+        }
+        let prefix = self.prefix.next_multiple_of(8);
+        let step = 1u32 << (32 - prefix);
+        (0..1u32 << (prefix - self.prefix))
+            .map(|i| Self { addr: IpAddr::V4((u32::from(addr) + i * step).into()), prefix })
+            .collect()
+    }
+
+    /// absolute name of the reverse zone, like `2.0.192.in-addr.arpa.`
+    /// or `64/26.2.0.192.in-addr.arpa.` for RFC 2317 networks,
+    /// the zone the network lies in when `zones` splits it
+    pub fn origin(&self) -> String {
+        let labels = reverse_labels(self.addr);
+        match self.addr {
+            IpAddr::V4(addr) if self.prefix > 24 => {
+                format!("{}/{}.{}", addr.octets()[3], self.prefix, labels[1..].join("."))
+            }
+            IpAddr::V4(_) => labels[4 - self.prefix as usize / 8..].join("."),
+            IpAddr::V6(_) => labels[32 - self.prefix as usize / 4..].join("."),
+        }
+    }
+
+    /// owner name of `ip` relative to `origin`, the last octet for RFC 2317 networks
+    pub(crate) fn relative_name(&self, ip: IpAddr) -> String {
+        let labels = reverse_labels(ip);
+        match ip {
+            IpAddr::V4(_) if self.prefix > 24 => labels[0].clone(),
+            IpAddr::V4(_) => labels[..4 - self.prefix as usize / 8].join("."),
+            IpAddr::V6(_) => labels[..32 - self.prefix as usize / 4].join("."),
+        }
+    }
+
+    /// `ip` with the host bits cleared
+    fn bits(&self, ip: IpAddr) -> u128 {
+        let width = if ip.is_ipv4() { 32 } else { 128 };
+        let mask = u128::MAX.checked_shl(width - self.prefix as u32).unwrap_or(0);
+        bits(ip) & mask
+    }
+}
+
+fn bits(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(addr) => u32::from(addr) as u128,
+        IpAddr::V6(addr) => u128::from(addr),
+    }
+}
+
+impl FromStr for ReverseNetwork {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = s.split_once('/').ok_or_else(|| format!("`{s}` is not a network like `192.0.2.0/24`"))?;
+        let addr = addr.parse().map_err(|_| format!("invalid address in `{s}`"))?;
+        let prefix = prefix.parse().map_err(|_| format!("invalid prefix length in `{s}`"))?;
+        Self::new(addr, prefix)
+    }
+}
+
+impl Display for ReverseNetwork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// labels of the full reverse name of `ip`, including `in-addr`/`ip6`, `arpa` and the root
+pub(crate) fn reverse_labels(ip: IpAddr) -> Vec<String> {
+    let mut ret: Vec<String> = match ip {
+        IpAddr::V4(addr) => addr.octets().iter().rev().map(u8::to_string).collect(),
+        IpAddr::V6(addr) => addr
+            .octets()
+            .iter()
+            .rev()
+            .flat_map(|b| [format!("{:x}", b & 0xf), format!("{:x}", b >> 4)])
+            .collect(),
+    };
+    ret.push(if ip.is_ipv4() { "in-addr" } else { "ip6" }.to_string());
+    ret.push("arpa".to_string());
+    ret.push(String::new());
+    ret
+}
+
+/// absolute reverse name of `ip`, like `1.2.0.192.in-addr.arpa.`
+pub(crate) fn reverse_name(ip: IpAddr) -> String {
+    reverse_labels(ip).join(".")
+}
+
+impl Ptr {
+    /// PTR record for `ip` pointing at `host`, the owner is relative to
+    /// `origin` when the reverse name is inside it and absolute otherwise
+    pub fn from_ip(ip: IpAddr, host: &str, origin: &str) -> Self {
+        let fullname = reverse_name(ip);
+        Ptr { name: name::relative(&fullname, origin), fullname, host: host.to_string(), ttl: None }
+    }
+
+    /// address the record is for, read from `fullname`. RFC 2317 names like
+    /// `65.64/26.2.0.192.in-addr.arpa.` are understood, `None` when the name
+    /// is not a complete in-addr.arpa or ip6.arpa name
+    pub fn ip(&self) -> Option<IpAddr> {
+        let fullname = self.fullname.strip_suffix('.').unwrap_or(&self.fullname).to_ascii_lowercase();
+        if let Some(labels) = fullname.strip_suffix(".in-addr.arpa") {
+            let mut labels: Vec<&str> = labels.split('.').collect();
+            if labels.len() == 5 && labels[1].contains('/') {
+                labels.remove(1);
+            }
+            if labels.len() != 4 {
+                return None;
+            }
+            let mut octets = [0u8; 4];
+            for (octet, label) in octets.iter_mut().rev().zip(labels) {
+                if label.len() > 1 && label.starts_with('0') {
+                    return None;
+                }
+                *octet = label.parse().ok()?;
+            }
+            Some(IpAddr::from(octets))
+        } else if let Some(labels) = fullname.strip_suffix(".ip6.arpa") {
+            let labels: Vec<&str> = labels.split('.').collect();
+            if labels.len() != 32 {
+                return None;
+            }
+            let mut bits = 0u128;
+            for label in labels.iter().rev() {
+                if label.len() != 1 {
+                    return None;
+                }
+                bits = bits << 4 | u128::from_str_radix(label, 16).ok()?;
+            }
+            Some(IpAddr::from(bits.to_be_bytes()))
+        } else {
+            None
+        }
+    }
+}
+
+impl DnsRecord {
+    /// reverse zones for `networks` with a PTR record for every A and AAAA
+    /// record of the `forward` zones inside them
+    ///
+    /// networks without a zone of their own give one zone for each of
+    /// `ReverseNetwork::zones`, an address inside several networks goes to
+    /// the most specific one.
+    /// `$TTL`, SOA and the apex NS records are copied from the first forward
+    /// zone with a SOA, with the names made absolute
+    pub fn reverse_zones(forward: &[DnsRecord], networks: &[ReverseNetwork]) -> Vec<DnsRecord> {
+        let mut addresses: Vec<(IpAddr, String, Option<u32>)> = Vec::new();
+        for zone in forward {
+            let origin = zone.zone_origin();
+            let a = zone.a.iter().flatten().map(|r| (&r.ip, &r.name, r.ttl));
+            let aaaa = zone.aaaa.iter().flatten().map(|r| (&r.ip, &r.name, r.ttl));
+            for (ip, owner, ttl) in a.chain(aaaa) {
+                if let Ok(ip) = ip.parse() {
+                    addresses.push((ip, name::fqdn(owner, &origin), ttl));
+                }
+            }
+        }
+        addresses.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        addresses.dedup_by(|a, b| a.0 == b.0 && a.1 == b.1);
+
+        let template = forward.iter().find(|z| z.soa.is_some());
+        let networks: Vec<ReverseNetwork> = networks.iter().flat_map(ReverseNetwork::zones).collect();
+        networks
+            .iter()
+            .map(|network| {
+                let origin = network.origin();
+                let ptr: Vec<Ptr> = addresses
+                    .iter()
+                    .filter(|(ip, _, _)| {
+                        let best = networks.iter().filter(|n| n.contains(*ip)).map(|n| n.prefix).max();
+                        network.contains(*ip) && best == Some(network.prefix)
+                    })
+                    .map(|(ip, host, ttl)| {
+                        let name = network.relative_name(*ip);
+                        Ptr { fullname: name::fqdn(&name, &origin), name, host: host.clone(), ttl: *ttl }
+                    })
+                    .collect();
+                let mut zone = DnsRecord {
+                    origin: Some(origin),
+                    ptr: (!ptr.is_empty()).then_some(ptr),
+                    ..Default::default()
+                };
+                if let Some(template) = template {
+                    let forward_origin = template.zone_origin();
+                    zone.ttl = template.ttl;
+                    zone.soa = template.soa.clone().map(|soa| Soa {
+                        name: "@".to_string(),
+                        mname: name::fqdn(&soa.mname, &forward_origin),
+                        rname: name::fqdn(&soa.rname, &forward_origin),
+                        ..soa
+                    });
+                    let ns: Vec<Ns> = template
+                        .ns
+                        .iter()
+                        .flatten()
+                        .filter(|r| name::fqdn(&r.name, &forward_origin).eq_ignore_ascii_case(&forward_origin))
+                        .map(|r| Ns { name: "@".to_string(), host: name::fqdn(&r.host, &forward_origin), ttl: r.ttl })
+                        .collect();
+                    zone.ns = (!ns.is_empty()).then_some(ns);
+                }
+                zone
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,115 @@
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Datelike, Utc};
+
+use crate::dns_structs::*;
+
+/// how `Soa::bump_serial` picks the next serial
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SerialStrategy {
+    /// seconds since the unix epoch
+    UnixTime,
+    /// `YYYYMMDDnn`, the date followed by a two digit counter
+    DateCounter,
+    /// the current serial plus one
+    Increment,
+}
+
+/// bump the SOA serial of the generated zone when its content changed
+#[derive(Debug, PartialEq, Clone)]
+pub struct SerialBump {
+    pub strategy: SerialStrategy,
+    /// the zone as it was last published, its serial is kept when nothing changed
+    pub previous: DnsRecord,
+}
+
+impl Serial {
+    /// numeric value of the serial, `{time}` and other strings are an error
+    pub fn number(&self) -> Result<u32, String> {
+        match self {
+            Serial::Number(n) => Ok(*n),
+            Serial::String(s) => s.parse().map_err(|_| format!("SOA serial `{s}` is not a number")),
+        }
+    }
+
+    /// compare in serial number arithmetic (RFC 1982), so a serial that wrapped
+    /// around is still newer. `None` for non numeric serials and for the
+    /// undefined case of two serials exactly 2^31 apart
+    pub fn compare(&self, other: &Serial) -> Option<Ordering> {
+        compare(self.number().ok()?, other.number().ok()?)
+    }
+}
+
+pub(crate) fn compare(s1: u32, s2: u32) -> Option<Ordering> {
+    const HALF: u32 = 1 << 31;
+    match s2.wrapping_sub(s1) {
+        0 => Some(Ordering::Equal),
+        HALF => None,
+        d if d < HALF => Some(Ordering::Less),
+        _ => Some(Ordering::Greater),
+    }
+}
+
+impl SerialStrategy {
+    /// next serial after `current`, always greater in serial number arithmetic
+    pub fn next(&self, current: u32, now: DateTime<Utc>) -> u32 {
+        let candidate = match self {
+            SerialStrategy::UnixTime => now.timestamp() as u32,
+            SerialStrategy::DateCounter => {
+                let date = now.year() as u32 * 10000 + now.month() * 100 + now.day();
+                date.wrapping_mul(100)
+            }
+            SerialStrategy::Increment => current.wrapping_add(1),
+        };
+        match compare(current, candidate) {
+            Some(Ordering::Less) => candidate,
+            _ => current.wrapping_add(1),
+        }
+    }
+}
+
+impl Soa {
+    /// replace the serial with the next one of `strategy` and return it
+    pub fn bump_serial(&mut self, strategy: SerialStrategy, now: DateTime<Utc>) -> Result<u32, String> {
+        let serial = strategy.next(self.serial.number()?, now);
+        self.serial = Serial::Number(serial);
+        Ok(serial)
+    }
+}
+
+impl SerialBump {
+    /// SOA of `zone` with the serial of the previous zone, bumped when
+    /// anything but the serial changed. a serial already newer than the
+    /// previous one is kept. records are compared in canonical form, so
+    /// relative or absolute names and an implicit or explicit ttl are no change
+    pub(crate) fn soa(&self, zone: &DnsRecord, now: DateTime<Utc>) -> Result<Option<Soa>, String> {
+        let (Some(soa), Some(previous_soa)) = (&zone.soa, &self.previous.soa) else {
+            return Ok(zone.soa.clone());
+        };
+        if soa.serial.compare(&previous_soa.serial) == Some(Ordering::Greater) {
+            return Ok(Some(soa.clone()));
+        }
+        let previous_serial = previous_soa.serial.number()?;
+        let mut unchanged = zone.clone();
+        if let Some(soa) = unchanged.soa.as_mut() {
+            soa.serial = previous_soa.serial.clone();
+        }
+        // zones with data that has no wire form are compared as they are
+        let same = match (content(&unchanged), content(&self.previous)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => unchanged == self.previous,
+        };
+        let mut soa = soa.clone();
+        soa.serial = if same {
+            Serial::Number(previous_serial)
+        } else {
+            Serial::Number(self.strategy.next(previous_serial, now))
+        };
+        Ok(Some(soa))
+    }
+}
+
+/// records of the zone in canonical wire format, owner names lower-cased
+fn content(zone: &DnsRecord) -> Result<Vec<Vec<u8>>, String> {
+    zone.canonical_records()?.iter().map(|r| r.to_wire()).collect()
+}
@@ -0,0 +1,504 @@
+use std::fmt::Display;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use crate::dns_structs::*;
+use crate::name;
+use crate::record::joined_text;
+
+/// most DNS lookups a policy may cause (RFC 7208 section 4.6.4)
+pub const SPF_LOOKUP_LIMIT: usize = 10;
+
+/// result of a directive whose mechanism matches
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Qualifier {
+    /// `+`, the default
+    Pass,
+    /// `-`
+    Fail,
+    /// `~`
+    SoftFail,
+    /// `?`
+    Neutral,
+}
+
+impl Qualifier {
+    fn symbol(&self) -> &'static str {
+        match self {
+            Qualifier::Pass => "",
+            Qualifier::Fail => "-",
+            Qualifier::SoftFail => "~",
+            Qualifier::Neutral => "?",
+        }
+    }
+}
+
+/// what a directive matches the sending host against
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Mechanism {
+    All,
+    Include(String),
+    A { domain: Option<String>, cidr4: Option<u8>, cidr6: Option<u8> },
+    Mx { domain: Option<String>, cidr4: Option<u8>, cidr6: Option<u8> },
+    Ptr(Option<String>),
+    Ip4 { addr: Ipv4Addr, prefix: Option<u8> },
+    Ip6 { addr: Ipv6Addr, prefix: Option<u8> },
+    Exists(String),
+}
+
+impl Mechanism {
+    /// the mechanism costs a DNS lookup
+    pub fn needs_lookup(&self) -> bool {
+        matches!(
+            self,
+            Mechanism::Include(_) | Mechanism::A { .. } | Mechanism::Mx { .. } | Mechanism::Ptr(_) | Mechanism::Exists(_)
+        )
+    }
+}
+
+/// a mechanism with its qualifier
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Directive {
+    pub qualifier: Qualifier,
+    pub mechanism: Mechanism,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Modifier {
+    Redirect(String),
+    Exp(String),
+    /// modifiers receivers ignore
+    Other { name: String, value: String },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Term {
+    Directive(Directive),
+    Modifier(Modifier),
+}
+
+/// a `v=spf1` policy (RFC 7208)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpfRecord {
+    /// directives and modifiers in the order they are written
+    pub terms: Vec<Term>,
+}
+
+impl SpfRecord {
+    pub fn directives(&self) -> impl Iterator<Item = &Directive> {
+        self.terms.iter().filter_map(|t| match t {
+            Term::Directive(d) => Some(d),
+            Term::Modifier(_) => None,
+        })
+    }
+
+    pub fn redirect(&self) -> Option<&str> {
+        self.terms.iter().find_map(|t| match t {
+            Term::Modifier(Modifier::Redirect(domain)) => Some(domain.as_str()),
+            _ => None,
+        })
+    }
+
+    /// terms of this record that cost a DNS lookup, included policies not counted
+    pub fn lookup_terms(&self) -> usize {
+        self.directives().filter(|d| d.mechanism.needs_lookup()).count() + usize::from(self.redirect().is_some())
+    }
+}
+
+/// true when `text` is an SPF policy, `v=spf1` followed by a space or nothing
+pub fn is_spf(text: &str) -> bool {
+    let text = text.as_bytes();
+    text.len() >= 6 && text[..6].eq_ignore_ascii_case(b"v=spf1") && (text.len() == 6 || text[6] == b' ')
+}
+
+impl FromStr for SpfRecord {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !is_spf(s) {
+            return Err("SPF record has to start with `v=spf1`".to_string());
+        }
+        let terms = s[6..].split(' ').filter(|t| !t.is_empty()).map(parse_term).collect::<Result<Vec<_>, _>>()?;
+        for modifier in ["redirect", "exp"] {
+            let count = terms
+                .iter()
+                .filter(|t| matches!(t, Term::Modifier(m) if m.name() == modifier))
+                .count();
+            if count > 1 {
+                return Err(format!("`{modifier}` modifier appears more than once"));
+            }
+        }
+        Ok(Self { terms })
+    }
+}
+
+impl Modifier {
+    fn name(&self) -> &str {
+        match self {
+            Modifier::Redirect(_) => "redirect",
+            Modifier::Exp(_) => "exp",
+            Modifier::Other { name, .. } => name,
+        }
+    }
+}
+
+fn parse_term(term: &str) -> Result<Term, String> {
+    if let Some((name, value)) = term.split_once('=') {
+        let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if valid_name {
+            let modifier = match name.to_ascii_lowercase().as_str() {
+                "redirect" => Modifier::Redirect(domain_spec(value, term)?),
+                "exp" => Modifier::Exp(domain_spec(value, term)?),
+                _ => {
+                    check_macros(value, term)?;
+                    Modifier::Other { name: name.to_string(), value: value.to_string() }
+                }
+            };
+            return Ok(Term::Modifier(modifier));
+        }
+    }
+    let (qualifier, rest) = match term.as_bytes()[0] {
+        b'+' => (Qualifier::Pass, &term[1..]),
+        b'-' => (Qualifier::Fail, &term[1..]),
+        b'~' => (Qualifier::SoftFail, &term[1..]),
+        b'?' => (Qualifier::Neutral, &term[1..]),
+        _ => (Qualifier::Pass, term),
+    };
+    let split = rest.find([':', '/']).unwrap_or(rest.len());
+    let (name, args) = rest.split_at(split);
+    let domain = |required: bool| match args.strip_prefix(':') {
+        Some(domain) => domain_spec(domain, term).map(Some),
+        None if required => Err(format!("`{term}` needs a domain")),
+        None => Ok(None),
+    };
+    let no_cidr = || if args.contains('/') { Err(format!("`{term}` can not have a prefix length")) } else { Ok(()) };
+    let mechanism = match name.to_ascii_lowercase().as_str() {
+        "all" if args.is_empty() => Mechanism::All,
+        "all" => return Err(format!("`{term}` takes no arguments")),
+        "include" => {
+            no_cidr()?;
+            Mechanism::Include(domain(true)?.unwrap_or_default())
+        }
+        "exists" => {
+            no_cidr()?;
+            Mechanism::Exists(domain(true)?.unwrap_or_default())
+        }
+        "ptr" => {
+            no_cidr()?;
+            Mechanism::Ptr(domain(false)?)
+        }
+        "a" => dual_cidr(args, term, false)?,
+        "mx" => dual_cidr(args, term, true)?,
+        "ip4" | "ip6" => {
+            let value = args.strip_prefix(':').ok_or_else(|| format!("`{term}` needs an address"))?;
+            let (addr, prefix) = match value.split_once('/') {
+                Some((addr, prefix)) => (addr, Some(cidr(prefix, if name.eq_ignore_ascii_case("ip4") { 32 } else { 128 }, term)?)),
+                None => (value, None),
+            };
+            let invalid = || format!("invalid address in `{term}`");
+            if name.eq_ignore_ascii_case("ip4") {
+                Mechanism::Ip4 { addr: addr.parse().map_err(|_| invalid())?, prefix }
+            } else {
+                Mechanism::Ip6 { addr: addr.parse().map_err(|_| invalid())?, prefix }
+            }
+        }
+        _ => return Err(format!("unknown mechanism `{term}`")),
+    };
+    Ok(Term::Directive(Directive { qualifier, mechanism }))
+}
+
+/// `a` or `mx` with an optional `:domain` followed by `/cidr4`, `//cidr6` or both
+fn dual_cidr(args: &str, term: &str, mx: bool) -> Result<Mechanism, String> {
+    let (domain, cidrs) = match args.strip_prefix(':') {
+        Some(rest) => {
+            let split = rest.find('/').unwrap_or(rest.len());
+            (Some(domain_spec(&rest[..split], term)?), &rest[split..])
+        }
+        None => (None, args),
+    };
+    let (cidr4, cidr6) = match cidrs.split_once("//") {
+        Some((v4, v6)) => (v4, Some(v6)),
+        None => (cidrs, None),
+    };
+    let cidr4 = match cidr4 {
+        "" => None,
+        v4 => Some(cidr(v4.strip_prefix('/').ok_or_else(|| format!("invalid prefix length in `{term}`"))?, 32, term)?),
+    };
+    let cidr6 = cidr6.map(|v6| cidr(v6, 128, term)).transpose()?;
+    Ok(if mx { Mechanism::Mx { domain, cidr4, cidr6 } } else { Mechanism::A { domain, cidr4, cidr6 } })
+}
+
+fn cidr(value: &str, max: u8, term: &str) -> Result<u8, String> {
+    match value.parse::<u8>() {
+        Ok(n) if n <= max && !value.starts_with('+') && (value == "0" || !value.starts_with('0')) => Ok(n),
+        _ => Err(format!("invalid prefix length in `{term}`")),
+    }
+}
+
+/// a domain with optional macros, names without macros need two labels
+fn domain_spec(value: &str, term: &str) -> Result<String, String> {
+    let has_macros = check_macros(value, term)?;
+    let labels: Vec<&str> = value.strip_suffix('.').unwrap_or(value).split('.').collect();
+    if !has_macros && (labels.len() < 2 || labels.iter().any(|l| l.is_empty() || l.len() > 63)) {
+        return Err(format!("invalid domain in `{term}`"));
+    }
+    Ok(value.to_string())
+}
+
+/// check the `%{...}` macros of a value (RFC 7208 section 7.1), true when it has any
+fn check_macros(value: &str, term: &str) -> Result<bool, String> {
+    let invalid = || format!("invalid macro in `{term}`");
+    let mut found = false;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            if !c.is_ascii_graphic() {
+                return Err(format!("invalid character in `{term}`"));
+            }
+            continue;
+        }
+        found = true;
+        match chars.next() {
+            Some('%' | '_' | '-') => {}
+            Some('{') => {
+                let body: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                let mut body = body.chars();
+                if !matches!(body.next().map(|c| c.to_ascii_lowercase()), Some('s' | 'l' | 'o' | 'd' | 'i' | 'p' | 'h' | 'c' | 'r' | 't' | 'v')) {
+                    return Err(invalid());
+                }
+                let rest: String = body.collect();
+                let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+                let rest = rest.strip_prefix(['r', 'R']).unwrap_or(rest);
+                if !rest.chars().all(|c| matches!(c, '.' | '-' | '+' | ',' | '/' | '_' | '=')) {
+                    return Err(invalid());
+                }
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(found)
+}
+
+impl Display for Mechanism {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dual = |f: &mut std::fmt::Formatter<'_>, name: &str, domain: &Option<String>, cidr4: &Option<u8>, cidr6: &Option<u8>| {
+            write!(f, "{name}")?;
+            if let Some(domain) = domain {
+                write!(f, ":{domain}")?;
+            }
+            if let Some(cidr4) = cidr4 {
+                write!(f, "/{cidr4}")?;
+            }
+            if let Some(cidr6) = cidr6 {
+                write!(f, "//{cidr6}")?;
+            }
+            Ok(())
+        };
+        match self {
+            Mechanism::All => write!(f, "all"),
+            Mechanism::Include(domain) => write!(f, "include:{domain}"),
+            Mechanism::Exists(domain) => write!(f, "exists:{domain}"),
+            Mechanism::Ptr(None) => write!(f, "ptr"),
+            Mechanism::Ptr(Some(domain)) => write!(f, "ptr:{domain}"),
+            Mechanism::A { domain, cidr4, cidr6 } => dual(f, "a", domain, cidr4, cidr6),
+            Mechanism::Mx { domain, cidr4, cidr6 } => dual(f, "mx", domain, cidr4, cidr6),
+            Mechanism::Ip4 { addr, prefix: None } => write!(f, "ip4:{addr}"),
+            Mechanism::Ip4 { addr, prefix: Some(prefix) } => write!(f, "ip4:{addr}/{prefix}"),
+            Mechanism::Ip6 { addr, prefix: None } => write!(f, "ip6:{addr}"),
+            Mechanism::Ip6 { addr, prefix: Some(prefix) } => write!(f, "ip6:{addr}/{prefix}"),
+        }
+    }
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Term::Directive(d) => write!(f, "{}{}", d.qualifier.symbol(), d.mechanism),
+            Term::Modifier(Modifier::Redirect(domain)) => write!(f, "redirect={domain}"),
+            Term::Modifier(Modifier::Exp(domain)) => write!(f, "exp={domain}"),
+            Term::Modifier(Modifier::Other { name, value }) => write!(f, "{name}={value}"),
+        }
+    }
+}
+
+impl Display for SpfRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v=spf1")?;
+        for term in &self.terms {
+            write!(f, " {term}")?;
+        }
+        Ok(())
+    }
+}
+
+/// an SPF policy published in a zone
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpfEntry {
+    /// absolute owner name
+    pub name: String,
+    /// `Txt`, or the obsolete `Spf` record type
+    pub rtype: RecordType,
+    pub record: Result<SpfRecord, String>,
+}
+
+/// result of checking the SPF policy of one owner
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SpfReport {
+    /// absolute owner name
+    pub name: String,
+    /// the policy receivers use, `None` when it does not parse
+    pub record: Option<SpfRecord>,
+    /// DNS lookups of the policy and every policy it includes that was found
+    pub lookups: usize,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl SpfReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl DnsRecord {
+    /// SPF policies of the zone, TXT records starting with `v=spf1` and SPF records
+    pub fn spf_records(&self) -> Vec<SpfEntry> {
+        let apex = self.zone_origin();
+        let txt = self.txt.iter().flatten().map(|r| (&r.name, RecordType::Txt, &r.txt));
+        let spf = self.spf.iter().flatten().map(|r| (&r.name, RecordType::Spf, &r.data));
+        txt.chain(spf)
+            .filter_map(|(owner, rtype, data)| {
+                let text = match joined_text(data) {
+                    Ok(text) if !is_spf(&text) => return None,
+                    Ok(text) => text,
+                    Err(e) if rtype == RecordType::Spf => return Some((owner, rtype, Err(e))),
+                    Err(_) => return None,
+                };
+                Some((owner, rtype, text.parse()))
+            })
+            .map(|(owner, rtype, record)| SpfEntry { name: name::fqdn(owner, &apex), rtype, record })
+            .collect()
+    }
+
+    /// check the SPF policy of every owner of the zone
+    ///
+    /// the syntax is checked against RFC 7208 and the DNS lookups are counted
+    /// against the limit of 10, following `include:` and `redirect=` into the
+    /// policies of this zone and `zones`. an `mx` with more than 10 hosts,
+    /// more than one policy at an owner and the obsolete SPF record type are
+    /// reported too
+    pub fn check_spf(&self, zones: &[DnsRecord]) -> Vec<SpfReport> {
+        let resolver = Resolver::new(self, zones);
+        let mut entries = self.spf_records();
+        entries.sort_by(|a, b| name::canonical_cmp(&a.name, &b.name));
+        let mut ret: Vec<SpfReport> = Vec::new();
+        for owner in entries.chunk_by(|a, b| name::canonical_cmp(&a.name, &b.name).is_eq()) {
+            let mut report = SpfReport { name: owner[0].name.clone(), ..Default::default() };
+            let txt: Vec<&SpfEntry> = owner.iter().filter(|e| e.rtype == RecordType::Txt).collect();
+            if txt.len() > 1 {
+                report.warnings.push(format!("{} SPF policies at one owner, receivers reject them all", txt.len()));
+            }
+            if owner.iter().any(|e| e.rtype == RecordType::Spf) {
+                report.warnings.push("the SPF record type is obsolete, publish the policy as TXT".to_string());
+            }
+            let policy = txt.first().copied().unwrap_or(&owner[0]);
+            match &policy.record {
+                Ok(record) => {
+                    let mut visited = vec![report.name.to_ascii_lowercase()];
+                    report.lookups = resolver.lookups(record, &report.name, &mut visited, &mut report.errors, &mut report.warnings);
+                    if report.lookups > SPF_LOOKUP_LIMIT {
+                        report.errors.push(format!("{} DNS lookups, more than the limit of {SPF_LOOKUP_LIMIT}", report.lookups));
+                    }
+                    style_warnings(record, &mut report.warnings);
+                    report.record = Some(record.clone());
+                }
+                Err(e) => report.errors.push(e.clone()),
+            }
+            ret.push(report);
+        }
+        ret
+    }
+}
+
+fn style_warnings(record: &SpfRecord, warnings: &mut Vec<String>) {
+    let all = record.terms.iter().position(|t| matches!(t, Term::Directive(Directive { mechanism: Mechanism::All, .. })));
+    if let Some(all) = all {
+        if record.terms[all + 1..].iter().any(|t| matches!(t, Term::Directive(_))) {
+            warnings.push("mechanisms after `all` are never reached".to_string());
+        }
+        if record.redirect().is_some() {
+            warnings.push("`redirect` is ignored next to `all`".to_string());
+        }
+        if matches!(&record.terms[all], Term::Directive(d) if d.qualifier == Qualifier::Pass) {
+            warnings.push("`+all` lets every host send mail".to_string());
+        }
+    }
+    if record.directives().any(|d| matches!(d.mechanism, Mechanism::Ptr(_))) {
+        warnings.push("`ptr` is slow and should not be used".to_string());
+    }
+}
+
+/// SPF policies and MX records of the zones lookups are followed into
+struct Resolver<'a> {
+    policies: Vec<SpfEntry>,
+    zones: Vec<&'a DnsRecord>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(zone: &'a DnsRecord, zones: &'a [DnsRecord]) -> Self {
+        let zones: Vec<&DnsRecord> = std::iter::once(zone).chain(zones).collect();
+        let policies = zones.iter().flat_map(|z| z.spf_records()).filter(|e| e.rtype == RecordType::Txt).collect();
+        Self { policies, zones }
+    }
+
+    fn policy(&self, domain: &str) -> Option<&Result<SpfRecord, String>> {
+        self.policies.iter().find(|e| name::canonical_cmp(&e.name, domain).is_eq()).map(|e| &e.record)
+    }
+
+    fn mx_hosts(&self, domain: &str) -> usize {
+        self.zones
+            .iter()
+            .map(|z| {
+                let apex = z.zone_origin();
+                z.mx.iter().flatten().filter(|mx| name::canonical_cmp(&name::fqdn(&mx.name, &apex), domain).is_eq()).count()
+            })
+            .sum()
+    }
+
+    fn lookups(&self, record: &SpfRecord, owner: &str, visited: &mut Vec<String>, errors: &mut Vec<String>, warnings: &mut Vec<String>) -> usize {
+        let mut ret = record.lookup_terms();
+        for term in &record.terms {
+            let target = match term {
+                Term::Directive(Directive { mechanism: Mechanism::Include(domain), .. }) => domain,
+                Term::Modifier(Modifier::Redirect(domain)) => domain,
+                Term::Directive(Directive { mechanism: Mechanism::Mx { domain, .. }, .. }) => {
+                    let domain = domain.as_deref().map_or_else(|| owner.to_string(), |d| name::fqdn(d, "."));
+                    if self.mx_hosts(&domain) > SPF_LOOKUP_LIMIT {
+                        errors.push(format!("`mx` of {domain} has more than {SPF_LOOKUP_LIMIT} hosts"));
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            if target.contains('%') {
+                warnings.push(format!("`{term}` uses macros, its lookups are not counted"));
+                continue;
+            }
+            let domain = name::fqdn(target, ".").to_ascii_lowercase();
+            if visited.contains(&domain) {
+                errors.push(format!("`{term}` loops back to {domain}"));
+                continue;
+            }
+            match self.policy(&domain) {
+                Some(Ok(included)) => {
+                    visited.push(domain.clone());
+                    ret += self.lookups(included, &domain, visited, errors, warnings);
+                    visited.pop();
+                }
+                Some(Err(e)) => errors.push(format!("policy of {domain}: {e}")),
+                None => warnings.push(format!("no policy for {domain} in the zones, its lookups are not counted")),
+            }
+        }
+        ret
+    }
+}
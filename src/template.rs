@@ -0,0 +1,256 @@
+//! small template language used by `generate`
+//!
+//! - `{var}` or `{var|filter|filter:arg}` substitutes a value
+//! - `{#each set}...{/each}` repeats the body for every record of a set,
+//!   the fields of the record are in scope inside
+//! - `{#if var}...{else}...{/if}` renders when the value or set is not empty
+//! - `{set}` on its own renders the lines the generator formatted for the set
+//! - `{{` is a literal `{`
+//!
+//! values are never scanned again, so a TXT record containing `{ns}` is
+//! written as is
+
+use std::io::{self, Write};
+
+use crate::name;
+
+/// a record set the template may loop over
+pub(crate) struct SetSchema {
+    pub name: &'static str,
+    pub fields: &'static [&'static str],
+}
+
+/// names a template may refer to
+pub(crate) struct Schema {
+    pub globals: &'static [&'static str],
+    pub sets: &'static [SetSchema],
+}
+
+/// values a template is rendered with, globals, sets and record fields in schema order
+///
+/// records are asked for one at a time so a zone is never held as text
+pub(crate) trait Data {
+    fn global(&self, global: usize) -> &str;
+    /// fields of the record at `index` of a set, `None` past the last one
+    fn record(&self, set: usize, index: usize) -> Option<Vec<String>>;
+    /// write the lines of a plain `{set}`, one per record
+    fn write_lines(&self, set: usize, out: &mut dyn Write) -> io::Result<()>;
+    /// origin `relative` strips from names
+    fn origin(&self) -> &str;
+}
+
+#[derive(Debug, PartialEq)]
+enum Filter {
+    Relative,
+    Upper,
+    Lower,
+    Pad(usize),
+}
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Global(usize, Vec<Filter>),
+    /// set index and field index
+    Field(usize, usize, Vec<Filter>),
+    Each(usize, Vec<Node>),
+    Lines(usize),
+    /// condition and both branches
+    If(Condition, Vec<Node>, Vec<Node>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Condition {
+    Global(usize),
+    Field(usize, usize),
+    Set(usize),
+}
+
+#[derive(Debug)]
+pub(crate) struct Template {
+    nodes: Vec<Node>,
+}
+
+/// a block being parsed: what opened it and the nodes so far
+struct Frame {
+    kind: Block,
+    nodes: Vec<Node>,
+    line: usize,
+}
+
+enum Block {
+    Root,
+    Each(usize),
+    If(Condition, Option<Vec<Node>>),
+}
+
+impl Template {
+    /// parse `source`, every placeholder and filter is checked against `schema`
+    pub fn parse(source: &str, schema: &Schema) -> Result<Self, String> {
+        let mut stack = vec![Frame { kind: Block::Root, nodes: Vec::new(), line: 1 }];
+        let mut text = String::new();
+        let mut line = 1;
+        let mut rest = source;
+        while let Some(start) = rest.find('{') {
+            text.push_str(&rest[..start]);
+            line += rest[..start].matches('\n').count();
+            rest = &rest[start..];
+            if let Some(after) = rest.strip_prefix("{{") {
+                text.push('{');
+                rest = after;
+                continue;
+            }
+            let end = rest
+                .find('}')
+                .ok_or_else(|| format!("unclosed placeholder on line {line}"))?;
+            let tag = rest[1..end].trim();
+            rest = &rest[end + 1..];
+            if !text.is_empty() {
+                stack.last_mut().unwrap().nodes.push(Node::Text(std::mem::take(&mut text)));
+            }
+            let error = |message: String| format!("{message} on line {line}");
+            let scope = current_set(&stack);
+
+            if let Some(set) = tag.strip_prefix("#each ") {
+                let set = set_index(schema, set.trim()).ok_or_else(|| error(format!("unknown record set `{}`", set.trim())))?;
+                stack.push(Frame { kind: Block::Each(set), nodes: Vec::new(), line });
+            } else if let Some(var) = tag.strip_prefix("#if ") {
+                let condition = condition(schema, scope, var.trim()).ok_or_else(|| error(format!("unknown placeholder `{}`", var.trim())))?;
+                stack.push(Frame { kind: Block::If(condition, None), nodes: Vec::new(), line });
+            } else if tag == "else" {
+                let frame = stack.last_mut().unwrap();
+                match &mut frame.kind {
+                    Block::If(_, then @ None) => *then = Some(std::mem::take(&mut frame.nodes)),
+                    _ => return Err(error("`{else}` outside of `{#if}`".to_owned())),
+                }
+            } else if tag == "/each" || tag == "/if" {
+                let frame = stack.pop().unwrap();
+                let node = match (frame.kind, tag) {
+                    (Block::Each(set), "/each") => Node::Each(set, frame.nodes),
+                    (Block::If(condition, None), "/if") => Node::If(condition, frame.nodes, Vec::new()),
+                    (Block::If(condition, Some(then)), "/if") => Node::If(condition, then, frame.nodes),
+                    _ => return Err(error(format!("unexpected `{{{tag}}}`"))),
+                };
+                stack.last_mut().unwrap().nodes.push(node);
+            } else {
+                let node = placeholder(schema, scope, tag).map_err(error)?;
+                stack.last_mut().unwrap().nodes.push(node);
+            }
+        }
+        text.push_str(rest);
+        if stack.len() > 1 {
+            return Err(format!("block opened on line {} is not closed", stack.last().unwrap().line));
+        }
+        let mut root = stack.pop().unwrap();
+        if !text.is_empty() {
+            root.nodes.push(Node::Text(text));
+        }
+        Ok(Self { nodes: root.nodes })
+    }
+
+    pub fn render(&self, data: &dyn Data, out: &mut dyn Write) -> io::Result<()> {
+        render(&self.nodes, data, None, out)
+    }
+}
+
+fn current_set(stack: &[Frame]) -> Option<usize> {
+    stack.iter().rev().find_map(|f| match f.kind {
+        Block::Each(set) => Some(set),
+        _ => None,
+    })
+}
+
+fn set_index(schema: &Schema, name: &str) -> Option<usize> {
+    schema.sets.iter().position(|s| s.name == name)
+}
+
+fn field_index(schema: &Schema, scope: Option<usize>, name: &str) -> Option<(usize, usize)> {
+    let set = scope?;
+    schema.sets[set].fields.iter().position(|f| *f == name).map(|f| (set, f))
+}
+
+/// fields of the enclosing loop shadow globals, which shadow sets
+fn condition(schema: &Schema, scope: Option<usize>, name: &str) -> Option<Condition> {
+    if let Some((set, field)) = field_index(schema, scope, name) {
+        return Some(Condition::Field(set, field));
+    }
+    if let Some(global) = schema.globals.iter().position(|g| *g == name) {
+        return Some(Condition::Global(global));
+    }
+    set_index(schema, name).map(Condition::Set)
+}
+
+fn placeholder(schema: &Schema, scope: Option<usize>, tag: &str) -> Result<Node, String> {
+    let mut parts = tag.split('|');
+    let name = parts.next().unwrap_or_default().trim();
+    let filters = parts.map(filter).collect::<Result<Vec<_>, _>>()?;
+    match condition(schema, scope, name) {
+        Some(Condition::Field(set, field)) => Ok(Node::Field(set, field, filters)),
+        Some(Condition::Global(global)) => Ok(Node::Global(global, filters)),
+        Some(Condition::Set(set)) if filters.is_empty() => Ok(Node::Lines(set)),
+        Some(Condition::Set(_)) => Err(format!("filters can not be applied to record set `{name}`")),
+        None => Err(format!("unknown placeholder `{{{tag}}}`")),
+    }
+}
+
+fn filter(spec: &str) -> Result<Filter, String> {
+    let (name, arg) = match spec.trim().split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg.trim())),
+        None => (spec.trim(), None),
+    };
+    match (name, arg) {
+        ("relative", None) => Ok(Filter::Relative),
+        ("upper", None) => Ok(Filter::Upper),
+        ("lower", None) => Ok(Filter::Lower),
+        ("pad", Some(width)) => width
+            .parse()
+            .map(Filter::Pad)
+            .map_err(|_| format!("invalid width `{width}` for filter `pad`")),
+        ("pad", None) => Err("filter `pad` needs a width, like `pad:8`".to_owned()),
+        _ => Err(format!("unknown filter `{}`", spec.trim())),
+    }
+}
+
+fn apply(value: &str, filters: &[Filter], data: &dyn Data) -> String {
+    let mut value = value.to_string();
+    for filter in filters {
+        value = match filter {
+            Filter::Relative => name::relative(&value, data.origin()),
+            Filter::Upper => value.to_uppercase(),
+            Filter::Lower => value.to_lowercase(),
+            Filter::Pad(width) => format!("{value:<width$}"),
+        };
+    }
+    value
+}
+
+fn render(nodes: &[Node], data: &dyn Data, record: Option<&[String]>, out: &mut dyn Write) -> io::Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.write_all(text.as_bytes())?,
+            Node::Global(global, filters) => out.write_all(apply(data.global(*global), filters, data).as_bytes())?,
+            Node::Field(_, field, filters) => {
+                let value = record.and_then(|r| r.get(*field)).map_or("", String::as_str);
+                out.write_all(apply(value, filters, data).as_bytes())?;
+            }
+            Node::Each(set, body) => {
+                for index in 0.. {
+                    let Some(fields) = data.record(*set, index) else {
+                        break;
+                    };
+                    render(body, data, Some(&fields), out)?;
+                }
+            }
+            Node::Lines(set) => data.write_lines(*set, out)?,
+            Node::If(condition, then, otherwise) => {
+                let truthy = match condition {
+                    Condition::Global(global) => !data.global(*global).is_empty(),
+                    Condition::Field(_, field) => record.and_then(|r| r.get(*field)).is_some_and(|v| !v.is_empty()),
+                    Condition::Set(set) => data.record(*set, 0).is_some(),
+                };
+                render(if truthy { then } else { otherwise }, data, record, out)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,344 @@
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::diff;
+use crate::dns_structs::*;
+use crate::encoding::{base64_decode, base64_encode};
+use crate::generator;
+use crate::name;
+use crate::patch::{PatchOperation, ZonePatch};
+use crate::record::Record;
+
+const CLASS_IN: u16 = 1;
+const CLASS_NONE: u16 = 254;
+const CLASS_ANY: u16 = 255;
+const TYPE_ANY: u16 = 255;
+const TYPE_TSIG: u16 = 250;
+const OPCODE_UPDATE: u16 = 5 << 11;
+/// seconds the TSIG time may be off on the server
+const TSIG_FUDGE: u16 = 300;
+/// longest message sent over UDP, longer ones go over TCP
+const UDP_SIZE: usize = 512;
+
+/// shared secret to sign updates with, HMAC-SHA256 TSIG (RFC 8945)
+#[derive(Debug, PartialEq, Clone)]
+pub struct TsigKey {
+    /// key name as the server knows it
+    pub name: String,
+    pub secret: Vec<u8>,
+}
+
+impl TsigKey {
+    pub const ALGORITHM: &'static str = "hmac-sha256.";
+
+    pub fn new(name: &str, secret: &[u8]) -> Self {
+        Self { name: name.to_string(), secret: secret.to_vec() }
+    }
+
+    /// key with a base64 secret as in `tsig-keygen` output
+    pub fn from_base64(name: &str, secret: &str) -> Result<Self, String> {
+        Ok(Self::new(name, &base64_decode(secret)?))
+    }
+}
+
+/// prerequisite of an update, checked by the server before any change
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature="serde", serde(tag = "check", rename_all = "kebab-case"))]
+pub enum Prerequisite {
+    /// the RRset of the records' owner and type holds exactly them (RFC 2136 section 2.4.2)
+    RrsetEquals { records: Vec<Record> },
+    /// there is no RRset of the type at the name (RFC 2136 section 2.4.3)
+    RrsetAbsent { name: String, rtype: RecordType },
+}
+
+/// RFC 2136 dynamic update of a zone on its primary
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct DnsUpdate {
+    /// absolute zone name
+    pub zone: String,
+    /// ttl of added records that have none
+    pub ttl: u32,
+    pub prerequisites: Vec<Prerequisite>,
+    pub operations: Vec<PatchOperation>,
+}
+
+impl DnsRecord {
+    /// update turning `self`, the zone as the primary serves it, into `new`
+    ///
+    /// every RRset that changes has to be on the server as it is in `self`,
+    /// or absent when `self` has none, so the update fails instead of
+    /// overwriting a change made in the meantime. SOA deletes are left out,
+    /// servers ignore them and replace the SOA with the added one
+    pub fn update_to(&self, new: &DnsRecord) -> DnsUpdate {
+        let diff = self.diff(new);
+        let mut old = diff::rrsets(self);
+        let prerequisites = diff
+            .rrsets
+            .iter()
+            .map(|rrset| match old.remove(&(rrset.name.to_ascii_lowercase(), rrset.rtype.code())) {
+                Some((_, entries)) => Prerequisite::RrsetEquals { records: entries.into_iter().map(|e| e.record).collect() },
+                None => Prerequisite::RrsetAbsent { name: rrset.name.clone(), rtype: rrset.rtype },
+            })
+            .collect();
+        let operations = ZonePatch::from(&diff)
+            .operations
+            .into_iter()
+            .filter(|op| !matches!(op, PatchOperation::Delete { record: Record::Soa(_) }))
+            .collect();
+        DnsUpdate { zone: self.zone_origin(), ttl: new.effective_ttl(None), prerequisites, operations }
+    }
+}
+
+impl DnsUpdate {
+    fn owner(&self, name: &str) -> String {
+        name::fqdn(name, &self.zone)
+    }
+
+    /// absolute owner, ttl, type and rdata of a record in presentation format
+    fn fields(&self, record: &Record) -> (String, u32, String, String) {
+        let ttl = record.ttl().unwrap_or(self.ttl);
+        let mut record = record.clone();
+        record.set_ttl(Some(ttl));
+        let line = generator::record_line(&record);
+        let mut fields = line.splitn(5, '\t');
+        let owner = self.owner(fields.next().unwrap_or_default());
+        let rtype = fields.nth(2).unwrap_or_default().to_string();
+        (owner, ttl, rtype, fields.next().unwrap_or_default().replace('\t', " "))
+    }
+
+    /// the update as an `nsupdate` script, sent to `server` and signed
+    /// with `key` when they are given
+    pub fn to_nsupdate(&self, server: Option<&str>, key: Option<&TsigKey>) -> String {
+        let mut ret = String::new();
+        if let Some(server) = server {
+            let _ = writeln!(ret, "server {server}");
+        }
+        if let Some(key) = key {
+            let _ = writeln!(ret, "key hmac-sha256:{} {}", key.name, base64_encode(&key.secret));
+        }
+        let _ = writeln!(ret, "zone {}", self.zone);
+        for prerequisite in &self.prerequisites {
+            match prerequisite {
+                Prerequisite::RrsetEquals { records } => {
+                    for record in records {
+                        let (owner, _, rtype, rdata) = self.fields(record);
+                        let _ = writeln!(ret, "prereq yxrrset {owner} {rtype} {rdata}");
+                    }
+                }
+                Prerequisite::RrsetAbsent { name, rtype } => {
+                    let _ = writeln!(ret, "prereq nxrrset {} {rtype}", self.owner(name));
+                }
+            }
+        }
+        let add = |ret: &mut String, record: &Record| {
+            let (owner, ttl, rtype, rdata) = self.fields(record);
+            let _ = writeln!(ret, "update add {owner} {ttl} {rtype} {rdata}");
+        };
+        for operation in &self.operations {
+            match operation {
+                PatchOperation::Add { record } => add(&mut ret, record),
+                PatchOperation::Delete { record } => {
+                    let (owner, _, rtype, rdata) = self.fields(record);
+                    let _ = writeln!(ret, "update delete {owner} {rtype} {rdata}");
+                }
+                PatchOperation::DeleteRrset { name, rtype } => {
+                    let _ = writeln!(ret, "update delete {} {rtype}", self.owner(name));
+                }
+                PatchOperation::DeleteName { name } => {
+                    let _ = writeln!(ret, "update delete {}", self.owner(name));
+                }
+                PatchOperation::ReplaceRrset { name, rtype, records } => {
+                    let _ = writeln!(ret, "update delete {} {rtype}", self.owner(name));
+                    records.iter().for_each(|r| add(&mut ret, r));
+                }
+            }
+        }
+        ret.push_str("send\n");
+        ret
+    }
+
+    /// unsigned UPDATE message with message id `id` (RFC 2136 section 2)
+    pub fn to_wire(&self, id: u16) -> Result<Vec<u8>, String> {
+        let mut prerequisites = Vec::new();
+        let mut prerequisite_count = 0;
+        for prerequisite in &self.prerequisites {
+            match prerequisite {
+                Prerequisite::RrsetEquals { records } => {
+                    for record in records {
+                        self.record_rr(&mut prerequisites, record, CLASS_IN, Some(0))?;
+                        prerequisite_count += 1;
+                    }
+                }
+                Prerequisite::RrsetAbsent { name, rtype } => {
+                    rr(&mut prerequisites, &self.owner(name), rtype.code(), CLASS_NONE, 0, &[])?;
+                    prerequisite_count += 1;
+                }
+            }
+        }
+        let mut updates = Vec::new();
+        let mut update_count = 0;
+        for operation in &self.operations {
+            match operation {
+                PatchOperation::Add { record } => self.record_rr(&mut updates, record, CLASS_IN, None)?,
+                PatchOperation::Delete { record } => self.record_rr(&mut updates, record, CLASS_NONE, Some(0))?,
+                PatchOperation::DeleteRrset { name, rtype } => {
+                    rr(&mut updates, &self.owner(name), rtype.code(), CLASS_ANY, 0, &[])?;
+                }
+                PatchOperation::DeleteName { name } => rr(&mut updates, &self.owner(name), TYPE_ANY, CLASS_ANY, 0, &[])?,
+                PatchOperation::ReplaceRrset { name, rtype, records } => {
+                    rr(&mut updates, &self.owner(name), rtype.code(), CLASS_ANY, 0, &[])?;
+                    for record in records {
+                        self.record_rr(&mut updates, record, CLASS_IN, None)?;
+                    }
+                    update_count += records.len();
+                }
+            }
+            update_count += 1;
+        }
+        let count = |n: usize| u16::try_from(n).map_err(|_| "too many records for one update message".to_string());
+
+        let mut ret = Vec::new();
+        ret.extend(id.to_be_bytes());
+        ret.extend(OPCODE_UPDATE.to_be_bytes());
+        for n in [1, count(prerequisite_count)?, count(update_count)?, 0] {
+            ret.extend(n.to_be_bytes());
+        }
+        ret.extend(name::to_wire(&self.zone, false)?);
+        ret.extend(RecordType::Soa.code().to_be_bytes());
+        ret.extend(CLASS_IN.to_be_bytes());
+        ret.extend(prerequisites);
+        ret.extend(updates);
+        Ok(ret)
+    }
+
+    /// UPDATE message signed with `key` at time `now` (RFC 8945 section 4)
+    pub fn to_signed_wire(&self, id: u16, key: &TsigKey, now: DateTime<Utc>) -> Result<Vec<u8>, String> {
+        let mut ret = self.to_wire(id)?;
+        let key_name = name::absolute_origin(&key.name);
+        let algorithm = name::to_wire(TsigKey::ALGORITHM, true)?;
+        let time = &now.timestamp().max(0).to_be_bytes()[2..];
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&key.secret).map_err(|e| e.to_string())?;
+        mac.update(&ret);
+        mac.update(&name::to_wire(&key_name, true)?);
+        mac.update(&CLASS_ANY.to_be_bytes());
+        mac.update(&0u32.to_be_bytes());
+        mac.update(&algorithm);
+        mac.update(time);
+        mac.update(&TSIG_FUDGE.to_be_bytes());
+        // error and other length
+        mac.update(&[0; 4]);
+        let mac = mac.finalize().into_bytes();
+
+        let mut rdata = algorithm;
+        rdata.extend(time);
+        rdata.extend(TSIG_FUDGE.to_be_bytes());
+        rdata.extend((mac.len() as u16).to_be_bytes());
+        rdata.extend(mac);
+        rdata.extend(id.to_be_bytes());
+        rdata.extend([0; 4]);
+        rr(&mut ret, &key_name, TYPE_TSIG, CLASS_ANY, 0, &rdata)?;
+        // the TSIG record is the only additional record
+        ret[10..12].copy_from_slice(&1u16.to_be_bytes());
+        Ok(ret)
+    }
+
+    /// send the update with message id `id` to `server` and wait up to
+    /// `timeout` for the answer, over TCP when the message does not fit a
+    /// 512 octet UDP datagram. `id` should come from a random source so
+    /// answers can not be guessed
+    ///
+    /// the message is signed with `key` when given, the TSIG of the answer
+    /// is not checked. an answer other than NOERROR is an error naming its rcode
+    pub fn send(&self, server: impl ToSocketAddrs, id: u16, key: Option<&TsigKey>, timeout: Duration) -> Result<(), String> {
+        let server = server.to_socket_addrs().map_err(|e| e.to_string())?.next().ok_or("no address for the server")?;
+        let message = match key {
+            Some(key) => self.to_signed_wire(id, key, Utc::now())?,
+            None => self.to_wire(id)?,
+        };
+        let answer = if message.len() > UDP_SIZE {
+            exchange_tcp(server, &message, timeout)
+        } else {
+            exchange_udp(server, &message, timeout)
+        }
+        .map_err(|e| format!("update to {server} failed: {e}"))?;
+        if answer.len() < 12 || answer[..2] != id.to_be_bytes() || answer[2] & 0x80 == 0 {
+            return Err(format!("malformed answer from {server}"));
+        }
+        match answer[3] & 0x0f {
+            0 => Ok(()),
+            rcode => Err(format!("update refused by {server} with {}", rcode_name(rcode))),
+        }
+    }
+
+    fn record_rr(&self, out: &mut Vec<u8>, record: &Record, class: u16, ttl: Option<u32>) -> Result<(), String> {
+        let ttl = ttl.unwrap_or_else(|| record.ttl().unwrap_or(self.ttl));
+        let rdata = record.rdata_wire(&self.zone)?;
+        rr(out, &self.owner(record.name()), record.record_type().code(), class, ttl, &rdata)
+    }
+}
+
+/// owner | type | class | ttl | rdlength | rdata, names uncompressed
+fn rr(out: &mut Vec<u8>, owner: &str, rtype: u16, class: u16, ttl: u32, rdata: &[u8]) -> Result<(), String> {
+    out.extend(name::to_wire(owner, false)?);
+    out.extend(rtype.to_be_bytes());
+    out.extend(class.to_be_bytes());
+    out.extend(ttl.to_be_bytes());
+    let len = u16::try_from(rdata.len()).map_err(|_| format!("RDATA of `{owner}` is too long"))?;
+    out.extend(len.to_be_bytes());
+    out.extend(rdata);
+    Ok(())
+}
+
+fn exchange_udp(server: SocketAddr, message: &[u8], timeout: Duration) -> std::io::Result<Vec<u8>> {
+    let local = if server.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = UdpSocket::bind(local)?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.connect(server)?;
+    socket.send(message)?;
+    let mut buf = vec![0; 65535];
+    let len = socket.recv(&mut buf)?;
+    buf.truncate(len);
+    Ok(buf)
+}
+
+/// messages over TCP are preceded by their length (RFC 1035 section 4.2.2)
+fn exchange_tcp(server: SocketAddr, message: &[u8], timeout: Duration) -> std::io::Result<Vec<u8>> {
+    let len = u16::try_from(message.len()).map_err(|_| std::io::Error::other("message longer than 65535 octets"))?;
+    let mut stream = TcpStream::connect_timeout(&server, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    stream.write_all(&[&len.to_be_bytes()[..], message].concat())?;
+    let mut len = [0; 2];
+    stream.read_exact(&mut len)?;
+    let mut buf = vec![0; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn rcode_name(rcode: u8) -> String {
+    match rcode {
+        1 => "FORMERR",
+        2 => "SERVFAIL",
+        3 => "NXDOMAIN",
+        4 => "NOTIMP",
+        5 => "REFUSED",
+        6 => "YXDOMAIN",
+        7 => "YXRRSET",
+        8 => "NXRRSET",
+        9 => "NOTAUTH",
+        10 => "NOTZONE",
+        _ => return format!("rcode {rcode}"),
+    }
+    .to_string()
+}
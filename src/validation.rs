@@ -0,0 +1,398 @@
+use chrono::{DateTime, Utc};
+use rsa::signature::Verifier;
+use rsa::{BigUint, RsaPublicKey};
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dns_structs::*;
+use crate::dnssec::{self, Rrset, ZoneCuts};
+use crate::encoding::{base32hex_decode, base32hex_encode};
+use crate::name;
+
+/// outcome of checking the signatures over one RRset
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub enum RrsetStatus {
+    /// at least one RRSIG validates, with the key tags of the keys that signed it
+    Valid { key_tags: Vec<u16> },
+    /// delegation NS or glue, not signed by the zone
+    Unsigned,
+    /// DS RRset whose signature validates but whose records could not be checked
+    /// because neither the zone nor the caller has the DNSKEY set of the child,
+    /// with the key tags of the keys that signed it
+    Unverified { key_tags: Vec<u16> },
+    /// no RRSIG covers the RRset
+    Missing,
+    /// RRSIGs exist but none of them validates, with the reason of the last one
+    Invalid(String),
+}
+
+/// signature check of one RRset
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct RrsetResult {
+    pub name: String,
+    pub rtype: RecordType,
+    pub status: RrsetStatus,
+}
+
+/// result of `DnsRecord::verify_dnssec`
+///
+/// `rrsets` holds one entry per RRset in canonical order, `errors` the problems
+/// found in the NSEC/NSEC3 chain, the DNSKEY set and the DS records
+#[derive(Debug, Default, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Deserialize, Serialize))]
+pub struct DnssecReport {
+    pub rrsets: Vec<RrsetResult>,
+    pub errors: Vec<String>,
+}
+
+impl DnssecReport {
+    /// every RRset the zone signs validates and no other problem was found,
+    /// DS records that could not be checked against a child key do not count
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+            && self.rrsets.iter().all(|r| {
+                matches!(r.status, RrsetStatus::Valid { .. } | RrsetStatus::Unverified { .. } | RrsetStatus::Unsigned)
+            })
+    }
+}
+
+impl DnsRecord {
+    /// check a signed zone before publishing it
+    ///
+    /// every authoritative RRset needs an RRSIG by an apex DNSKEY that is valid at `now`,
+    /// the NSEC or NSEC3 chain has to cover every name and close at the end,
+    /// the DNSKEY RRset has to be signed by a key with the SEP flag when there is one,
+    /// and DS records have to match a DNSKEY of the child when the zone carries its keys,
+    /// DS RRsets of other children are `RrsetStatus::Unverified`
+    pub fn verify_dnssec(&self, now: DateTime<Utc>) -> Result<DnssecReport, String> {
+        self.verify_dnssec_with(now, &[])
+    }
+
+    /// `verify_dnssec` with the DNSKEY records of child zones, DS records at a
+    /// delegation are checked against the `child_keys` with the same owner
+    pub fn verify_dnssec_with(&self, now: DateTime<Utc>, child_keys: &[Dnskey]) -> Result<DnssecReport, String> {
+        let apex = self.zone_origin();
+        let all = self.rrsets()?;
+        let cuts = ZoneCuts::new(&apex, &all);
+        let keys: Vec<Dnskey> = self
+            .dnskey
+            .iter()
+            .flatten()
+            .filter(|k| name::canonical_cmp(&name::fqdn(&k.name, &apex), &apex).is_eq())
+            .cloned()
+            .collect();
+        let rrsigs: Vec<(String, &Rrsig)> = self
+            .rrsig
+            .iter()
+            .flatten()
+            .map(|r| (name::fqdn(&r.name, &apex), r))
+            .collect();
+        let now = now.timestamp() as u32;
+
+        let mut report = DnssecReport::default();
+        if keys.is_empty() {
+            report.errors.push("no DNSKEY records at the zone apex".to_owned());
+        }
+        for rrset in all.iter().filter(|r| r.rtype != RecordType::Rrsig) {
+            let status = if cuts.is_signed(rrset) {
+                let covering: Vec<&Rrsig> = rrsigs
+                    .iter()
+                    .filter(|(owner, r)| r.type_covered == rrset.rtype && name::canonical_cmp(owner, &rrset.name).is_eq())
+                    .map(|(_, r)| *r)
+                    .collect();
+                rrset_status(rrset, &covering, &keys, &apex, now)
+            } else {
+                RrsetStatus::Unsigned
+            };
+            report.rrsets.push(RrsetResult { name: rrset.name.clone(), rtype: rrset.rtype, status });
+        }
+        for (owner, rrsig) in &rrsigs {
+            let covered = all
+                .iter()
+                .any(|r| r.rtype == rrsig.type_covered && name::canonical_cmp(owner, &r.name).is_eq());
+            if !covered {
+                report.errors.push(format!("RRSIG at {owner} covers {} which does not exist", rrsig.type_covered));
+            }
+        }
+
+        let sep_tags: Vec<u16> = keys.iter().filter(|k| k.is_sep()).map(Dnskey::key_tag).collect();
+        let dnskey_signers = report
+            .rrsets
+            .iter()
+            .find(|r| r.rtype == RecordType::Dnskey && name::canonical_cmp(&r.name, &apex).is_eq())
+            .and_then(|r| match &r.status {
+                RrsetStatus::Valid { key_tags } => Some(key_tags),
+                _ => None,
+            });
+        if let Some(signers) = dnskey_signers
+            && !sep_tags.is_empty()
+            && !signers.iter().any(|t| sep_tags.contains(t))
+        {
+            report.errors.push("DNSKEY RRset is not signed by a key with the SEP flag".to_owned());
+        }
+
+        let data: Vec<Rrset> = all
+            .into_iter()
+            .filter(|r| !matches!(r.rtype, RecordType::Rrsig | RecordType::Nsec | RecordType::Nsec3))
+            .collect();
+        match (&self.nsec, &self.nsec3) {
+            (None, None) => report.errors.push("zone has no NSEC or NSEC3 chain".to_owned()),
+            (Some(nsec), _) => self.check_nsec_chain(nsec, &cuts, &data, &mut report.errors),
+            (None, Some(nsec3)) => self.check_nsec3_chain(nsec3, &cuts, &data, &mut report.errors)?,
+        }
+        let unverified = self.check_ds(child_keys, &mut report.errors);
+        for rrset in report.rrsets.iter_mut().filter(|r| r.rtype == RecordType::Ds) {
+            if let RrsetStatus::Valid { key_tags } = &rrset.status
+                && unverified.iter().any(|owner| name::canonical_cmp(owner, &rrset.name).is_eq())
+            {
+                rrset.status = RrsetStatus::Unverified { key_tags: key_tags.clone() };
+            }
+        }
+        Ok(report)
+    }
+
+    fn check_nsec_chain(&self, nsec: &[Nsec], cuts: &ZoneCuts, data: &[Rrset], errors: &mut Vec<String>) {
+        let names = cuts.chain_names(data);
+        for (i, owner) in names.iter().enumerate() {
+            let Some(record) = nsec.iter().find(|n| name::canonical_cmp(&name::fqdn(&n.name, &cuts.apex), owner).is_eq()) else {
+                errors.push(format!("{owner} has no NSEC record"));
+                continue;
+            };
+            let next = &names[(i + 1) % names.len()];
+            if !name::canonical_cmp(&name::fqdn(&record.next_domain, &cuts.apex), next).is_eq() {
+                errors.push(format!("NSEC at {owner} points to {}, expected {next}", record.next_domain));
+            }
+            check_types("NSEC", owner, &record.types, &cuts.nsec_types(owner, data), errors);
+        }
+        for record in nsec {
+            let owner = name::fqdn(&record.name, &cuts.apex);
+            if !names.iter().any(|n| name::canonical_cmp(n, &owner).is_eq()) {
+                errors.push(format!("NSEC at {owner} does not belong to the chain"));
+            }
+        }
+    }
+
+    fn check_nsec3_chain(&self, nsec3: &[Nsec3], cuts: &ZoneCuts, data: &[Rrset], errors: &mut Vec<String>) -> Result<(), String> {
+        let param = self
+            .nsec3param
+            .iter()
+            .flatten()
+            .find(|p| name::canonical_cmp(&name::fqdn(&p.name, &cuts.apex), &cuts.apex).is_eq());
+        let (hash_algorithm, iterations, salt) = match (param, nsec3.first()) {
+            (Some(p), _) => (p.hash_algorithm, p.iterations, p.salt.clone()),
+            (None, Some(first)) => {
+                errors.push("NSEC3 chain without NSEC3PARAM at the zone apex".to_owned());
+                (first.hash_algorithm, first.iterations, first.salt.clone())
+            }
+            (None, None) => {
+                errors.push("zone has no NSEC or NSEC3 chain".to_owned());
+                return Ok(());
+            }
+        };
+        if hash_algorithm != 1 {
+            errors.push(format!("unsupported NSEC3 hash algorithm {hash_algorithm}"));
+            return Ok(());
+        }
+
+        // hash of the first label and the record, sorted like the chain
+        let mut chain = Vec::with_capacity(nsec3.len());
+        for record in nsec3 {
+            let owner = name::fqdn(&record.name, &cuts.apex);
+            if record.hash_algorithm != hash_algorithm || record.iterations != iterations || record.salt != salt {
+                errors.push(format!("NSEC3 at {owner} does not use the NSEC3PARAM hash parameters"));
+            }
+            let label = owner.split('.').next().unwrap_or_default();
+            match base32hex_decode(label) {
+                Ok(hash) => chain.push((hash, record)),
+                Err(_) => errors.push(format!("NSEC3 owner {owner} is not a hashed name")),
+            }
+        }
+        chain.sort_by(|a, b| a.0.cmp(&b.0));
+        for (i, (_, record)) in chain.iter().enumerate() {
+            let next = &chain[(i + 1) % chain.len()].0;
+            if &record.next_hashed != next {
+                errors.push(format!(
+                    "NSEC3 at {} points to {}, expected {}",
+                    record.name,
+                    base32hex_encode(&record.next_hashed),
+                    base32hex_encode(next)
+                ));
+            }
+        }
+
+        let opt_out = nsec3.iter().any(|n| n.flags & 1 != 0);
+        let names = cuts.chain_names(data);
+        let empty_non_terminals = cuts.empty_non_terminals(&names);
+        let mut expected = Vec::new();
+        for owner in names.iter().chain(&empty_non_terminals) {
+            expected.push((dnssec::nsec3_hash(owner, &salt, iterations)?, owner));
+        }
+        for (hash, owner) in &expected {
+            match chain.iter().find(|(h, _)| h == hash) {
+                Some((_, record)) => check_types("NSEC3", owner, &record.types, &cuts.nsec3_types(owner, data), errors),
+                None => {
+                    let insecure = cuts.is_delegation(owner) && !cuts.authoritative_types(owner, data).contains(&RecordType::Ds);
+                    if !(opt_out && insecure) {
+                        errors.push(format!("{owner} has no NSEC3 record"));
+                    }
+                }
+            }
+        }
+        for (hash, record) in &chain {
+            if !expected.iter().any(|(h, _)| h == hash) {
+                errors.push(format!("NSEC3 at {} matches no name in the zone", record.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// check DS records against the keys of the zone and `child_keys`,
+    /// returns the owners of DS records no key was found for
+    fn check_ds(&self, child_keys: &[Dnskey], errors: &mut Vec<String>) -> Vec<String> {
+        let origin = self.zone_origin();
+        let mut unverified = Vec::new();
+        // malformed DS records are reported by `validate`
+        for ds in self.ds.iter().flatten().filter(|d| d.raw.is_none()) {
+            let owner = name::fqdn(&ds.name, &origin);
+            let keys: Vec<&Dnskey> = self
+                .dnskey
+                .iter()
+                .flatten()
+                .chain(child_keys)
+                .filter(|k| name::canonical_cmp(&name::fqdn(&k.name, &origin), &owner).is_eq())
+                .collect();
+            // keys of a child zone are usually not part of the parent
+            if keys.is_empty() {
+                unverified.push(owner);
+                continue;
+            }
+            let mut matched = false;
+            for key in keys {
+                if key.key_tag() != ds.key_tag || key.algorithm != ds.algorithm {
+                    continue;
+                }
+                if let Ok(computed) = key.to_ds(&origin, ds.digest_type) {
+                    matched |= computed.digest == ds.digest;
+                }
+            }
+            if !matched {
+                errors.push(format!("DS {} at {owner} matches no DNSKEY", ds.key_tag));
+            }
+        }
+        unverified
+    }
+}
+
+fn check_types(kind: &str, owner: &str, types: &[RecordType], expected: &[RecordType], errors: &mut Vec<String>) {
+    let mut types = types.to_vec();
+    types.sort_by_key(RecordType::code);
+    types.dedup();
+    if types != expected {
+        let list = |t: &[RecordType]| t.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(" ");
+        errors.push(format!("{kind} at {owner} lists `{}`, expected `{}`", list(&types), list(expected)));
+    }
+}
+
+fn rrset_status(rrset: &Rrset, rrsigs: &[&Rrsig], keys: &[Dnskey], apex: &str, now: u32) -> RrsetStatus {
+    if rrsigs.is_empty() {
+        return RrsetStatus::Missing;
+    }
+    let mut key_tags = Vec::new();
+    let mut reason = String::new();
+    for rrsig in rrsigs {
+        match check_rrsig(rrset, rrsig, keys, apex, now) {
+            Ok(()) => key_tags.push(rrsig.key_tag),
+            Err(e) => reason = e,
+        }
+    }
+    if key_tags.is_empty() {
+        RrsetStatus::Invalid(reason)
+    } else {
+        RrsetStatus::Valid { key_tags }
+    }
+}
+
+fn check_rrsig(rrset: &Rrset, rrsig: &Rrsig, keys: &[Dnskey], apex: &str, now: u32) -> Result<(), String> {
+    if !name::canonical_cmp(&name::fqdn(&rrsig.signer_name, apex), apex).is_eq() {
+        return Err(format!("RRSIG signer {} is not the zone apex", rrsig.signer_name));
+    }
+    if rrsig.labels > dnssec::rrsig_labels(&rrset.name)? {
+        return Err(format!("RRSIG labels {} exceed the owner name", rrsig.labels));
+    }
+    // RFC 1982 serial number arithmetic, the fields wrap in 2106
+    if (now.wrapping_sub(rrsig.inception) as i32) < 0 {
+        return Err(format!("RRSIG is not valid before {}", crate::encoding::dnssec_time_encode(rrsig.inception)));
+    }
+    if (rrsig.expiration.wrapping_sub(now) as i32) < 0 {
+        return Err(format!("RRSIG expired at {}", crate::encoding::dnssec_time_encode(rrsig.expiration)));
+    }
+    let data = dnssec::signed_data(rrsig, rrset)?;
+    let mut reason = format!("no DNSKEY with key tag {} and algorithm {}", rrsig.key_tag, rrsig.algorithm);
+    for key in keys {
+        if key.key_tag() != rrsig.key_tag || key.algorithm != rrsig.algorithm || !key.is_zone_key() || key.protocol != 3 {
+            continue;
+        }
+        match verify_signature(key, &data, &rrsig.signature) {
+            Ok(()) => return Ok(()),
+            Err(e) => reason = e,
+        }
+    }
+    Err(reason)
+}
+
+fn verify_signature(key: &Dnskey, data: &[u8], signature: &[u8]) -> Result<(), String> {
+    match key.algorithm {
+        DnssecAlgorithm::EcdsaP256Sha256 => {
+            let point = [&[4], key.public_key.as_slice()].concat();
+            let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&point).map_err(|e| e.to_string())?;
+            let signature = p256::ecdsa::Signature::from_slice(signature).map_err(|e| e.to_string())?;
+            key.verify(data, &signature).map_err(|_| "bad signature".to_owned())
+        }
+        DnssecAlgorithm::EcdsaP384Sha384 => {
+            let point = [&[4], key.public_key.as_slice()].concat();
+            let key = p384::ecdsa::VerifyingKey::from_sec1_bytes(&point).map_err(|e| e.to_string())?;
+            let signature = p384::ecdsa::Signature::from_slice(signature).map_err(|e| e.to_string())?;
+            key.verify(data, &signature).map_err(|_| "bad signature".to_owned())
+        }
+        DnssecAlgorithm::Ed25519 => {
+            let public_key: [u8; 32] = key
+                .public_key
+                .as_slice()
+                .try_into()
+                .map_err(|_| "Ed25519 public key must be 32 octets".to_owned())?;
+            let key = ed25519_dalek::VerifyingKey::from_bytes(&public_key).map_err(|e| e.to_string())?;
+            let signature = ed25519_dalek::Signature::from_slice(signature).map_err(|e| e.to_string())?;
+            key.verify(data, &signature).map_err(|_| "bad signature".to_owned())
+        }
+        DnssecAlgorithm::RsaSha1 | DnssecAlgorithm::RsaSha1Nsec3Sha1 => {
+            rsa_verify::<sha1::Sha1>(&key.public_key, data, signature)
+        }
+        DnssecAlgorithm::RsaSha256 => rsa_verify::<sha2::Sha256>(&key.public_key, data, signature),
+        DnssecAlgorithm::RsaSha512 => rsa_verify::<sha2::Sha512>(&key.public_key, data, signature),
+        other => Err(format!("unsupported DNSSEC algorithm {other}")),
+    }
+}
+
+/// RSA/SHA-x with the public key format of RFC 3110
+fn rsa_verify<D>(public_key: &[u8], data: &[u8], signature: &[u8]) -> Result<(), String>
+where
+    D: sha2::Digest + rsa::pkcs8::AssociatedOid,
+{
+    let (exponent_len, rest) = match public_key {
+        [0, hi, lo, rest @ ..] => (u16::from_be_bytes([*hi, *lo]) as usize, rest),
+        [len, rest @ ..] => (*len as usize, rest),
+        [] => return Err("empty RSA public key".to_owned()),
+    };
+    if rest.len() <= exponent_len {
+        return Err("truncated RSA public key".to_owned());
+    }
+    let (exponent, modulus) = rest.split_at(exponent_len);
+    let key = RsaPublicKey::new(BigUint::from_bytes_be(modulus), BigUint::from_bytes_be(exponent))
+        .map_err(|e| e.to_string())?;
+    let signature = rsa::pkcs1v15::Signature::try_from(signature).map_err(|e| e.to_string())?;
+    rsa::pkcs1v15::VerifyingKey::<D>::new(key)
+        .verify(data, &signature)
+        .map_err(|_| "bad signature".to_owned())
+}
@@ -0,0 +1,133 @@
+use sha2::{Digest, Sha384, Sha512};
+
+use crate::dns_structs::*;
+use crate::name;
+
+/// ZONEMD scheme (RFC 8976 section 2.2.2)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ZonemdScheme {
+    Simple,
+}
+
+impl From<ZonemdScheme> for u8 {
+    fn from(value: ZonemdScheme) -> Self {
+        match value {
+            ZonemdScheme::Simple => 1,
+        }
+    }
+}
+
+impl TryFrom<u8> for ZonemdScheme {
+    type Error = String;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Simple),
+            _ => Err(format!("unsupported ZONEMD scheme {value}")),
+        }
+    }
+}
+
+/// ZONEMD hash algorithm (RFC 8976 section 2.2.3)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ZonemdHashAlgorithm {
+    Sha384,
+    Sha512,
+}
+
+impl From<ZonemdHashAlgorithm> for u8 {
+    fn from(value: ZonemdHashAlgorithm) -> Self {
+        match value {
+            ZonemdHashAlgorithm::Sha384 => 1,
+            ZonemdHashAlgorithm::Sha512 => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for ZonemdHashAlgorithm {
+    type Error = String;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Sha384),
+            2 => Ok(Self::Sha512),
+            _ => Err(format!("unsupported ZONEMD hash algorithm {value}")),
+        }
+    }
+}
+
+impl DnsRecord {
+    /// compute the ZONEMD record of this zone (RFC 8976)
+    ///
+    /// every in-zone record is digested in canonical form and order,
+    /// except the ZONEMD records at the apex themselves.
+    /// the SOA serial has to be numeric since it is copied into the result
+    pub fn compute_zonemd(&self, scheme: ZonemdScheme, hash_alg: ZonemdHashAlgorithm) -> Result<Zonemd, String> {
+        let soa = self.soa.as_ref().ok_or("ZONEMD needs an SOA record")?;
+        let serial = soa.serial.number()?;
+        let digest = self.zonemd_digest(scheme, hash_alg)?;
+        Ok(Zonemd {
+            name: soa.name.clone(),
+            serial,
+            scheme: scheme.into(),
+            hash_algorithm: hash_alg.into(),
+            digest,
+            ttl: soa.ttl,
+        })
+    }
+
+    /// check the ZONEMD records at the zone apex against the zone content
+    ///
+    /// succeeds when one record with a supported scheme and hash algorithm
+    /// matches, as RFC 8976 section 4 asks
+    pub fn verify_zonemd(&self) -> Result<(), String> {
+        let soa = self.soa.as_ref().ok_or("ZONEMD needs an SOA record")?;
+        let origin = self.zone_origin();
+        let apex: Vec<&Zonemd> = self
+            .zonemd
+            .iter()
+            .flatten()
+            .filter(|z| name::canonical_cmp(&name::fqdn(&z.name, &origin), &origin).is_eq())
+            .collect();
+        if apex.is_empty() {
+            return Err("no ZONEMD record at the zone apex".to_owned());
+        }
+        let mut error = "no ZONEMD record with a supported scheme and hash algorithm".to_owned();
+        for zonemd in apex {
+            let (Ok(scheme), Ok(hash_alg)) = (
+                ZonemdScheme::try_from(zonemd.scheme),
+                ZonemdHashAlgorithm::try_from(zonemd.hash_algorithm),
+            ) else {
+                continue;
+            };
+            if soa.serial.to_string() != zonemd.serial.to_string() {
+                error = format!("ZONEMD serial {} does not match SOA serial {}", zonemd.serial, soa.serial);
+                continue;
+            }
+            if self.zonemd_digest(scheme, hash_alg)? == zonemd.digest {
+                return Ok(());
+            }
+            error = "ZONEMD digest does not match the zone content".to_owned();
+        }
+        Err(error)
+    }
+
+    fn zonemd_digest(&self, scheme: ZonemdScheme, hash_alg: ZonemdHashAlgorithm) -> Result<Vec<u8>, String> {
+        let ZonemdScheme::Simple = scheme;
+        let origin = self.zone_origin();
+        let zonemd_type = RecordType::Zonemd.code();
+        let rrsig_type = RecordType::Rrsig.code();
+        let mut data = Vec::new();
+        for rr in self.canonical_records()? {
+            // the apex ZONEMD RRset and its signatures are not digested
+            let apex = name::canonical_cmp(&rr.name, &origin).is_eq();
+            let covers_zonemd = rr.rtype == rrsig_type && rr.rdata.starts_with(&zonemd_type.to_be_bytes());
+            if apex && (rr.rtype == zonemd_type || covers_zonemd) {
+                continue;
+            }
+            data.extend(rr.to_wire()?);
+        }
+        Ok(match hash_alg {
+            ZonemdHashAlgorithm::Sha384 => Sha384::digest(&data).to_vec(),
+            ZonemdHashAlgorithm::Sha512 => Sha512::digest(&data).to_vec(),
+        })
+    }
+}